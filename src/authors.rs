@@ -0,0 +1,335 @@
+use crate::cli::OutputFormat;
+use crate::de::{Affiliation, EnumAuthor};
+use crate::files::OutputFile;
+use crate::output::RecordWriter;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// what makes two author entries of different articles the same author: the ORCID when there is one, otherwise the
+/// normalized name and first affiliation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum AuthorKey {
+    Orcid(String),
+    Name { last_name: String, fore_name: String, initials: String, affiliation: String },
+    Collective(String),
+}
+
+/// lowercase alphanumeric words separated by single spaces, so that `Smith, J.` and `smith j` match
+fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `https://orcid.org/0000-0001-2345-678x` and `0000-0001-2345-678X` are the same ORCID. the same goes for
+/// `https://ror.org/05gq02987` and `05GQ02987`
+fn normalize_id(s: &str) -> String {
+    let s = s.trim();
+    let s = s.rsplit_once('/').map_or(s, |(_, id)| id);
+    s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_uppercase()
+}
+
+impl From<&EnumAuthor> for AuthorKey {
+    fn from(value: &EnumAuthor) -> Self {
+        match value {
+            EnumAuthor::Person { last_name, fore_name, initials, identifiers, affiliation, .. } => {
+                match identifiers.get("ORCID").map(|id| normalize_id(id)).filter(|id| !id.is_empty()) {
+                    Some(orcid) => AuthorKey::Orcid(orcid),
+                    None => AuthorKey::Name {
+                        last_name: normalize(last_name),
+                        fore_name: normalize(fore_name),
+                        initials: normalize(initials),
+                        affiliation: affiliation.first().map(|a| normalize(&a.name)).unwrap_or_default(),
+                    },
+                }
+            }
+            EnumAuthor::Collective { collective_name, .. } => AuthorKey::Collective(normalize(collective_name)),
+        }
+    }
+}
+
+/// what makes two affiliations the same institution: the first of its ROR, GRID or ISNI ids, otherwise the normalized
+/// name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum InstitutionKey {
+    Id { source: &'static str, id: String },
+    Name(String),
+}
+
+impl InstitutionKey {
+    /// `None` for an affiliation without a name or an id
+    fn new(affiliation: &Affiliation) -> Option<Self> {
+        let id = ["ROR", "GRID", "ISNI"].into_iter().find_map(|source| {
+            let id = normalize_id(affiliation.identifiers.get(source)?);
+            (!id.is_empty()).then_some(InstitutionKey::Id { source, id })
+        });
+        id.or_else(|| {
+            let name = normalize(&affiliation.name);
+            (!name.is_empty()).then_some(InstitutionKey::Name(name))
+        })
+    }
+}
+
+/// the distinct authors and institutions of a single job, and which article they wrote
+#[derive(Debug, Default)]
+pub(crate) struct AuthorLinks {
+    /// the first entry seen of each author, without its affiliations, and the index into institutions of every
+    /// affiliation seen for it
+    authors: Vec<(AuthorKey, EnumAuthor, Vec<usize>)>,
+    index: HashMap<AuthorKey, usize>,
+    /// the first entry seen of each institution, with the identifiers of later entries
+    institutions: Vec<(InstitutionKey, Affiliation)>,
+    institution_index: HashMap<InstitutionKey, usize>,
+    /// `(pmid, position in the author list, index into authors, equal contribution)`
+    links: Vec<(u64, usize, usize, bool)>,
+}
+
+impl AuthorLinks {
+    pub(crate) fn add(&mut self, pmid: u64, authors: &[EnumAuthor]) {
+        for (position, author) in authors.iter().enumerate() {
+            let key = AuthorKey::from(author);
+            let index = match self.index.get(&key) {
+                Some(index) => *index,
+                None => {
+                    // equal contribution is about the article, not the author. it goes to the link table instead.
+                    // affiliations go to the institution table
+                    let mut author = author.clone();
+                    match &mut author {
+                        EnumAuthor::Person { equal_contrib, affiliation, .. } => {
+                            *equal_contrib = false;
+                            affiliation.clear();
+                        }
+                        EnumAuthor::Collective { equal_contrib, .. } => *equal_contrib = false,
+                    }
+                    self.index.insert(key.clone(), self.authors.len());
+                    self.authors.push((key, author, vec![]));
+                    self.authors.len() - 1
+                }
+            };
+            let equal_contrib = match author {
+                EnumAuthor::Person { affiliation, equal_contrib, .. } => {
+                    for affiliation in affiliation {
+                        if let Some(institution) = self.institution(affiliation) {
+                            let institutions = &mut self.authors[index].2;
+                            if !institutions.contains(&institution) {
+                                institutions.push(institution);
+                            }
+                        }
+                    }
+                    *equal_contrib
+                }
+                EnumAuthor::Collective { equal_contrib, .. } => *equal_contrib,
+            };
+            self.links.push((pmid, position, index, equal_contrib));
+        }
+    }
+
+    /// the index of the institution of `affiliation`, recording it when it is new
+    fn institution(&mut self, affiliation: &Affiliation) -> Option<usize> {
+        let key = InstitutionKey::new(affiliation)?;
+        match self.institution_index.get(&key) {
+            Some(&index) => {
+                merge_identifiers(&mut self.institutions[index].1, affiliation);
+                Some(index)
+            }
+            None => {
+                self.institution_index.insert(key.clone(), self.institutions.len());
+                self.institutions.push((key, affiliation.clone()));
+                Some(self.institutions.len() - 1)
+            }
+        }
+    }
+}
+
+/// adds the identifiers of `other` that `kept` does not have
+fn merge_identifiers(kept: &mut Affiliation, other: &Affiliation) {
+    for (source, id) in &other.identifiers {
+        kept.identifiers.entry(source.clone()).or_insert_with(|| id.clone());
+    }
+}
+
+/// links of every successful job, with the job index. only filled with --authors. grows with the whole corpus, as ids
+/// can only be assigned once every job is done
+pub(crate) static AUTHORS: Mutex<Vec<(usize, AuthorLinks)>> = Mutex::new(vec![]);
+
+#[derive(Serialize)]
+struct AuthorRecord<'a> {
+    id: u64,
+    #[serde(flatten)]
+    author: &'a EnumAuthor,
+    /// ids into the institution table, in order of first appearance
+    #[serde(skip_serializing_if = "<[u64]>::is_empty")]
+    institution_ids: &'a [u64],
+}
+
+#[derive(Serialize)]
+struct InstitutionRecord<'a> {
+    id: u64,
+    #[serde(flatten)]
+    institution: &'a Affiliation,
+}
+
+#[derive(Serialize)]
+struct LinkRecord {
+    pmid: u64,
+    author_id: u64,
+    /// 0 for the first author
+    position: usize,
+    #[serde(skip_serializing_if = "is_false")]
+    equal_contrib: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+/// the number of rows written by [write_tables]
+#[derive(Debug)]
+pub(crate) struct TableCounts {
+    pub(crate) authors: usize,
+    pub(crate) institutions: usize,
+    pub(crate) links: usize,
+}
+
+/// assigns ids to the distinct authors and institutions of every job, in job order so that the same inputs always get
+/// the same ids, and writes the author table to `authors`, the institution table to `institutions` and the
+/// article-author link table to `links`
+pub(crate) fn write_tables(
+    mut jobs: Vec<(usize, AuthorLinks)>,
+    authors: &Path,
+    institutions: &Path,
+    links: &Path,
+    format: OutputFormat,
+    gzip: bool,
+) -> io::Result<TableCounts> {
+    jobs.sort_by_key(|(index, _)| *index);
+    let mut link_writer = RecordWriter::new(OutputFile::create(links, gzip)?, format);
+    // authors and institutions are only written at the end, as later jobs add to them
+    let mut all_authors: Vec<(EnumAuthor, Vec<u64>)> = vec![];
+    let mut author_ids: HashMap<AuthorKey, usize> = HashMap::new();
+    let mut all_institutions: Vec<Affiliation> = vec![];
+    let mut institution_ids: HashMap<InstitutionKey, usize> = HashMap::new();
+    let mut link_count = 0;
+    for (_, job) in jobs {
+        let local_institutions: Vec<u64> = job
+            .institutions
+            .into_iter()
+            .map(|(key, institution)| {
+                let index = match institution_ids.get(&key) {
+                    Some(&index) => {
+                        merge_identifiers(&mut all_institutions[index], &institution);
+                        index
+                    }
+                    None => {
+                        institution_ids.insert(key, all_institutions.len());
+                        all_institutions.push(institution);
+                        all_institutions.len() - 1
+                    }
+                };
+                index as u64 + 1
+            })
+            .collect();
+        let mut local_ids = Vec::with_capacity(job.authors.len());
+        for (key, author, institutions) in job.authors {
+            let index = match author_ids.get(&key) {
+                Some(&index) => index,
+                None => {
+                    author_ids.insert(key, all_authors.len());
+                    all_authors.push((author, vec![]));
+                    all_authors.len() - 1
+                }
+            };
+            for institution in institutions {
+                let ids = &mut all_authors[index].1;
+                if !ids.contains(&local_institutions[institution]) {
+                    ids.push(local_institutions[institution]);
+                }
+            }
+            local_ids.push(index as u64 + 1);
+        }
+        for (pmid, position, index, equal_contrib) in job.links {
+            let record = LinkRecord { pmid, author_id: local_ids[index], position, equal_contrib };
+            link_writer.write_record(&serde_json::to_string(&record)?)?;
+            link_count += 1;
+        }
+    }
+    link_writer.finish()?.finish()?;
+    let mut author_writer = RecordWriter::new(OutputFile::create(authors, gzip)?, format);
+    for (index, (author, institution_ids)) in all_authors.iter().enumerate() {
+        let record = AuthorRecord { id: index as u64 + 1, author, institution_ids };
+        author_writer.write_record(&serde_json::to_string(&record)?)?;
+    }
+    author_writer.finish()?.finish()?;
+    let mut institution_writer = RecordWriter::new(OutputFile::create(institutions, gzip)?, format);
+    for (index, institution) in all_institutions.iter().enumerate() {
+        let record = InstitutionRecord { id: index as u64 + 1, institution };
+        institution_writer.write_record(&serde_json::to_string(&record)?)?;
+    }
+    institution_writer.finish()?.finish()?;
+    Ok(TableCounts { authors: all_authors.len(), institutions: all_institutions.len(), links: link_count })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::AuthorList;
+
+    fn authors(xml: &str) -> Vec<EnumAuthor> {
+        quick_xml::de::from_str::<AuthorList>(&format!("<AuthorList>{}</AuthorList>", xml)).unwrap().author
+    }
+
+    #[test]
+    fn same_author_across_articles() {
+        let mut links = AuthorLinks::default();
+        links.add(1, &authors(
+            r#"<Author><LastName>Smith</LastName><ForeName>J.</ForeName></Author>
+            <Author><LastName>Doe</LastName><Identifier Source="ORCID">https://orcid.org/0000-0001-2345-678x</Identifier></Author>"#,
+        ));
+        links.add(2, &authors(
+            r#"<Author><LastName>Doe</LastName><ForeName>Jane</ForeName><Identifier Source="ORCID">0000-0001-2345-678X</Identifier></Author>
+            <Author><LastName>SMITH</LastName><ForeName>J</ForeName></Author>
+            <Author><LastName>Smith</LastName><ForeName>J</ForeName><AffiliationInfo><Affiliation>Univ A</Affiliation></AffiliationInfo></Author>"#,
+        ));
+        assert_eq!(links.authors.len(), 3);
+        assert_eq!(links.authors[1].0, AuthorKey::Orcid("000000012345678X".to_string()));
+        let links: Vec<_> = links.links.iter().map(|(pmid, position, index, _)| (*pmid, *position, *index)).collect();
+        assert_eq!(links, vec![(1, 0, 0), (1, 1, 1), (2, 0, 1), (2, 1, 0), (2, 2, 2)]);
+    }
+
+    #[test]
+    fn institutions_by_id_or_name() {
+        let mut links = AuthorLinks::default();
+        links.add(1, &authors(
+            r#"<Author><LastName>Smith</LastName><AffiliationInfo><Affiliation>Univ A.</Affiliation></AffiliationInfo>
+            <AffiliationInfo><Affiliation>Dept X, Univ B</Affiliation><Identifier Source="ROR">https://ror.org/05gq02987</Identifier></AffiliationInfo></Author>"#,
+        ));
+        links.add(2, &authors(
+            r#"<Author><LastName>Doe</LastName><AffiliationInfo><Affiliation>University B</Affiliation><Identifier Source="ROR">05GQ02987</Identifier><Identifier Source="GRID">grid.40263.33</Identifier></AffiliationInfo>
+            <AffiliationInfo><Affiliation>univ a</Affiliation></AffiliationInfo></Author>
+            <Author><LastName>Smith</LastName><AffiliationInfo><Affiliation>Univ A</Affiliation></AffiliationInfo>
+            <AffiliationInfo><Affiliation>Univ C</Affiliation></AffiliationInfo></Author>"#,
+        ));
+        let keys: Vec<_> = links.institutions.iter().map(|(key, _)| key.clone()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                InstitutionKey::Name("univ a".to_string()),
+                InstitutionKey::Id { source: "ROR", id: "05GQ02987".to_string() },
+                InstitutionKey::Name("univ c".to_string()),
+            ]
+        );
+        assert_eq!(links.institutions[1].1.name, "Dept X, Univ B");
+        assert_eq!(links.institutions[1].1.identifiers.len(), 2);
+        let institutions: Vec<_> = links.authors.iter().map(|(_, _, institutions)| institutions.clone()).collect();
+        // the second Smith is the first one, by the name of their first affiliation
+        assert_eq!(institutions, vec![vec![0, 1, 2], vec![1, 0]]);
+        assert!(matches!(&links.authors[0].1, EnumAuthor::Person { affiliation, .. } if affiliation.is_empty()));
+    }
+}
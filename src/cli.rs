@@ -0,0 +1,206 @@
+use crate::files::base_name;
+use clap::{Parser, ValueEnum};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Parse PubMed baseline .xml files into .ndjson files")]
+pub(crate) struct Cli {
+    /// directory containing the baseline files, or a glob pattern such as `data/pubmed24n00*.xml.gz`
+    #[arg(short, long, default_value = ".")]
+    pub(crate) input: String,
+    /// file name prefix used together with --from and --count
+    #[arg(long, default_value = "pubmed24n")]
+    pub(crate) prefix: String,
+    /// first file number of the range, i.e. 1 for `pubmed24n0001.xml` or `pubmed24n0001.xml.gz`
+    #[arg(long, default_value_t = 1)]
+    pub(crate) from: usize,
+    /// number of files in the range. [from, from + count - 1]
+    #[arg(long, default_value_t = 100)]
+    pub(crate) count: usize,
+    /// explicit file list, relative to --input. overrides --from and --count
+    pub(crate) files: Vec<PathBuf>,
+    /// directory or glob pattern of daily update files, applied in file name order on top of the inputs. only the
+    /// latest version of each PMID is emitted and PMIDs in `<DeleteCitation>` are dropped. references are left as they
+    /// are, see --strict-refs. takes a first pass over every input
+    #[arg(long, value_name = "DIR|GLOB")]
+    pub(crate) updates: Option<String>,
+    /// output directory. defaults to the directory of each input file
+    #[arg(short, long)]
+    pub(crate) output: Option<PathBuf>,
+    /// number of worker threads. defaults to the number of logical CPUs
+    #[arg(short = 'j', long)]
+    pub(crate) threads: Option<usize>,
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Ndjson)]
+    pub(crate) format: OutputFormat,
+    /// gzip-compress the output files
+    #[arg(long)]
+    pub(crate) gzip: bool,
+    /// verify each input against its `.md5` sidecar file and skip corrupted ones
+    #[arg(long)]
+    pub(crate) verify: bool,
+    /// drop references to PMIDs above this one. `auto` uses the largest PMID emitted by this run, which takes a first
+    /// pass over every input. `none` keeps every reference
+    #[arg(long, value_name = "PMID|auto|none", default_value = "none", value_parser = parse_ref_cutoff)]
+    pub(crate) ref_cutoff: RefCutoff,
+    /// keep only references and relations to PMIDs that are emitted by this run, and report the others in
+    /// `{prefix}.dangling.tsv`. takes a first pass over every input
+    #[arg(long)]
+    pub(crate) strict_refs: bool,
+    /// resolve references that have no PMID by their DOI or PMCID, when the cited article is in the processed
+    /// inputs. takes a first pass over every input
+    #[arg(long)]
+    pub(crate) crosswalk: bool,
+    /// parse one `<PubmedArticle>` at a time instead of loading whole files into memory
+    #[arg(long)]
+    pub(crate) stream: bool,
+    /// assign ids to distinct authors across every input, by ORCID or else by normalized name and affiliation, and
+    /// write them to `authors.{ext}` with the article-author links in `article_authors.{ext}` and their affiliations in
+    /// `institutions.{ext}`. every distinct author and every link is kept in memory until the end of the run, even
+    /// with --stream
+    #[arg(long)]
+    pub(crate) authors: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    /// one JSON object per line
+    Ndjson,
+    /// a single JSON array per file
+    Json,
+}
+
+impl OutputFormat {
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RefCutoff {
+    Auto,
+    None,
+    Pmid(u64),
+}
+
+fn parse_ref_cutoff(s: &str) -> Result<RefCutoff, String> {
+    match s {
+        "auto" => Ok(RefCutoff::Auto),
+        "none" => Ok(RefCutoff::None),
+        s => s.parse().map(RefCutoff::Pmid).map_err(|_| format!("expected a PMID, `auto` or `none`, got {}", s)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Job {
+    /// position in the processing order. later jobs supersede earlier ones when --updates is given
+    pub(crate) index: usize,
+    pub(crate) input: PathBuf,
+    pub(crate) output: PathBuf,
+    /// `<PubmedBookArticle>`s, in the same format as `output`
+    pub(crate) books: PathBuf,
+    /// quarantine file for articles that failed to parse
+    pub(crate) rejected: PathBuf,
+    /// report of references dropped by --strict-refs
+    pub(crate) dangling: PathBuf,
+}
+
+fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+fn glob_inputs(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let paths = glob::glob(pattern).map_err(|e| format!("invalid glob {}: {}", pattern, e))?;
+    let mut inputs = paths.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// every `.xml` and `.xml.gz` file in `dir`, sorted by name
+fn dir_inputs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut inputs = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.ends_with(".xml") || name.ends_with(".xml.gz")
+        })
+        .collect::<Vec<_>>();
+    inputs.sort();
+    Ok(inputs)
+}
+
+impl Cli {
+    /// resolve input files. returns the first number of the range (0 for glob and explicit list) and the jobs
+    pub(crate) fn jobs(&self) -> Result<(usize, Vec<Job>), String> {
+        let (start, mut inputs): (usize, Vec<PathBuf>) = if !self.files.is_empty() {
+            let base = Path::new(&self.input);
+            (0, self.files.iter().map(|f| base.join(f)).collect())
+        } else if is_glob(&self.input) {
+            (0, glob_inputs(&self.input)?)
+        } else {
+            let base = Path::new(&self.input);
+            (
+                self.from,
+                (self.from..self.from + self.count)
+                    .map(|i| {
+                        let xml = base.join(format!("{}{:0>4}.xml", self.prefix, i));
+                        let gz = xml.with_extension("xml.gz");
+                        if !xml.exists() && gz.exists() { gz } else { xml }
+                    })
+                    .collect(),
+            )
+        };
+        if inputs.is_empty() {
+            return Err(format!("no input files matched {}", self.input));
+        }
+        if let Some(updates) = &self.updates {
+            match is_glob(updates) {
+                true => inputs.append(&mut glob_inputs(updates)?),
+                false => inputs.append(&mut dir_inputs(Path::new(updates))?),
+            }
+        }
+        Ok((start, inputs.into_iter().enumerate().map(|(index, input)| self.job(index, input)).collect()))
+    }
+
+    /// where files built from every input, such as `journals.ndjson`, go: next to the outputs of `jobs`, that is
+    /// --output or the directory of the first input
+    pub(crate) fn corpus_dir(&self, jobs: &[Job]) -> PathBuf {
+        match (&self.output, jobs.first()) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(job)) => job.output.parent().map(Path::to_path_buf).unwrap_or_default(),
+            (None, None) => PathBuf::new(),
+        }
+    }
+
+    /// a file built from every input, in the output format
+    pub(crate) fn corpus_output(&self, jobs: &[Job], name: &str) -> PathBuf {
+        let dir = self.corpus_dir(jobs);
+        match self.gzip {
+            true => dir.join(format!("{}.{}.gz", name, self.format.extension())),
+            false => dir.join(format!("{}.{}", name, self.format.extension())),
+        }
+    }
+
+    fn job(&self, index: usize, input: PathBuf) -> Job {
+        let extension = self.format.extension();
+        let dir = match &self.output {
+            Some(dir) => dir.clone(),
+            None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let base = base_name(&input);
+        let output = match self.gzip {
+            true => dir.join(format!("{}.{}.gz", base, extension)),
+            false => dir.join(format!("{}.{}", base, extension)),
+        };
+        let books = match self.gzip {
+            true => dir.join(format!("{}.books.{}.gz", base, extension)),
+            false => dir.join(format!("{}.books.{}", base, extension)),
+        };
+        let rejected = dir.join(format!("{}.rejected.xml", base));
+        let dangling = dir.join(format!("{}.dangling.tsv", base));
+        Job { index, input, output, books, rejected, dangling }
+    }
+}
@@ -0,0 +1,279 @@
+use crate::de::{ArticleId, DeleteCitation, PubmedArticle, PubmedBookArticle, PMID};
+use crate::stats::STATS;
+use crate::stream::ElementSplitter;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+/// corpus-wide knowledge gathered by a first pass over every input, before any output is written
+#[derive(Debug, Default)]
+pub(crate) struct Corpus {
+    /// PMIDs of every article that will be emitted
+    pub(crate) ids: HashSet<u64>,
+    /// with --updates, where the latest version of each PMID is. deleted PMIDs are absent
+    pub(crate) latest: Option<HashMap<u64, Version>>,
+    /// with --crosswalk, the PMID of each DOI and PMCID, keyed by [crosswalk_key]
+    pub(crate) crosswalk: HashMap<String, u64>,
+    /// whether references to PMIDs outside of `ids` are dropped
+    pub(crate) filter_refs: bool,
+}
+
+/// only set when a first pass was requested
+pub(crate) static CORPUS: OnceLock<Corpus> = OnceLock::new();
+
+/// the `index`-th `<PubmedArticle>` (or `<PubmedBookArticle>`) of the `file`-th input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Version {
+    pub(crate) file: u32,
+    pub(crate) index: u32,
+    /// false if the article fails to deserialize and will be rejected
+    ok: bool,
+}
+
+#[derive(Debug)]
+enum Entry {
+    /// `aliases` are the [crosswalk_key]s of the article, only collected with --crosswalk
+    Article { id: u64, index: u32, ok: bool, aliases: Vec<String> },
+    Delete(Vec<u64>),
+}
+
+/// `doi:10.1000/xyz` or `pmc:PMC123`, for the id types that can stand in for a PMID. DOIs are case-insensitive
+pub(crate) fn crosswalk_key(id: &ArticleId) -> Option<String> {
+    let value = id.id.as_deref()?.trim();
+    match id.ty.as_str() {
+        "doi" => {
+            let doi = value.trim_start_matches("https://doi.org/").trim_start_matches("doi:");
+            Some(format!("doi:{}", doi.to_lowercase())).filter(|_| !doi.is_empty())
+        }
+        "pmc" => {
+            let pmc = value.to_uppercase();
+            let digits = pmc.trim_start_matches("PMC");
+            (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then(|| format!("pmc:PMC{}", digits))
+        }
+        _ => None,
+    }
+}
+
+impl Corpus {
+    /// `inputs` are `(file index, path)` in processing order. with `updates`, later inputs supersede earlier ones.
+    /// with `crosswalk`, the DOIs and PMCIDs of every article are indexed too. unreadable inputs are ignored here, they
+    /// will be reported by the actual run.
+    pub(crate) fn build(inputs: &[(usize, &Path)], updates: bool, crosswalk: bool) -> Corpus {
+        let entries: Vec<(usize, Vec<Entry>)> = inputs
+            .par_iter()
+            .map(|(file, input)| {
+                let entries = crate::files::open_input(input).ok().map(|r| scan(r, crosswalk));
+                (*file, entries.unwrap_or_default())
+            })
+            .collect();
+        Corpus::from_entries(entries, updates)
+    }
+
+    /// the corpus of the `(file index, entries)` of every input, in processing order
+    fn from_entries(entries: Vec<(usize, Vec<Entry>)>, updates: bool) -> Corpus {
+        if !updates {
+            let mut aliases = HashMap::new();
+            let ids = entries.into_iter()
+                .flat_map(|(_, entries)| entries)
+                .filter_map(|e| match e {
+                    Entry::Article { id, ok: true, aliases: keys, .. } => {
+                        aliases.extend(keys.into_iter().map(|key| (key, id)));
+                        Some(id)
+                    }
+                    _ => None,
+                })
+                .collect();
+            return Corpus { ids, latest: None, crosswalk: aliases, filter_refs: false };
+        }
+
+        let mut latest = HashMap::new();
+        let mut aliases = HashMap::new();
+        for (file, entries) in entries {
+            for e in entries {
+                match e {
+                    Entry::Article { id, index, ok, aliases: keys } => {
+                        latest.insert(id, Version { file: file as u32, index, ok });
+                        if ok {
+                            aliases.extend(keys.into_iter().map(|key| (key, id)));
+                        }
+                    }
+                    Entry::Delete(ids) => {
+                        for id in ids {
+                            if latest.remove(&id).is_some() {
+                                STATS.deleted_citations.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let ids: HashSet<u64> = latest.iter().filter(|(_, v)| v.ok).map(|(id, _)| *id).collect();
+        // DOIs of deleted articles must not resolve to them
+        aliases.retain(|_, id| ids.contains(id));
+        Corpus { ids, latest: Some(latest), crosswalk: aliases, filter_refs: false }
+    }
+
+    pub(crate) fn contains(&self, id: u64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// the PMID of the article with this DOI or PMCID, if it is in the corpus
+    pub(crate) fn resolve(&self, id: &ArticleId) -> Option<u64> {
+        crosswalk_key(id).and_then(|key| self.crosswalk.get(&key).copied())
+    }
+
+    /// whether the `index`-th article of the `file`-th input is the latest version of `id`. always true without
+    /// --updates
+    pub(crate) fn is_latest(&self, id: u64, file: usize, index: usize) -> bool {
+        match &self.latest {
+            Some(latest) => latest.get(&id).is_some_and(|v| v.file as usize == file && v.index as usize == index),
+            None => true,
+        }
+    }
+}
+
+/// PMIDs of articles and deletions, in document order. with `crosswalk`, also the DOIs and PMCIDs of the articles.
+/// stops at malformed XML, keeping the entries before it like the actual run does
+fn scan<R: BufRead>(reader: R, crosswalk: bool) -> Vec<Entry> {
+    // enough to find the PMID of an article that fails to deserialize as a whole
+    #[derive(Deserialize)]
+    #[serde(rename_all(deserialize = "PascalCase"))]
+    struct IdOnly {
+        medline_citation: IdOnlyCitation,
+    }
+    #[derive(Deserialize)]
+    struct IdOnlyCitation {
+        #[serde(rename(deserialize = "PMID"))]
+        id: PMID,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all(deserialize = "PascalCase"))]
+    struct BookIdOnly {
+        book_document: IdOnlyCitation,
+    }
+
+    let aliases = |ids: &[ArticleId]| match crosswalk {
+        true => ids.iter().filter_map(crosswalk_key).collect(),
+        false => vec![],
+    };
+    let mut entries = vec![];
+    // books are counted apart from articles, like the emitting side does
+    let (mut articles, mut books) = (0, 0);
+    for element in ElementSplitter::new(reader) {
+        let Ok(element) = element else { break };
+        match element.name.as_str() {
+            "PubmedArticle" => {
+                let index = articles;
+                let entry = match quick_xml::de::from_str::<PubmedArticle>(&element.xml) {
+                    Ok(article) => Some(Entry::Article {
+                        id: article.medline_citation.id.id,
+                        index,
+                        ok: true,
+                        aliases: aliases(&article.pubmed_data.article_id_list.article_id),
+                    }),
+                    Err(_) => quick_xml::de::from_str::<IdOnly>(&element.xml).ok().map(|article| Entry::Article {
+                        id: article.medline_citation.id.id,
+                        index,
+                        ok: false,
+                        aliases: vec![],
+                    }),
+                };
+                entries.extend(entry);
+                articles += 1;
+            }
+            "PubmedBookArticle" => {
+                let index = books;
+                let entry = match quick_xml::de::from_str::<PubmedBookArticle>(&element.xml) {
+                    Ok(book) => Some(Entry::Article {
+                        id: book.book_document.id.id,
+                        index,
+                        ok: true,
+                        aliases: aliases(&book.pubmed_book_data.article_id_list.article_id),
+                    }),
+                    Err(_) => quick_xml::de::from_str::<BookIdOnly>(&element.xml).ok().map(|book| Entry::Article {
+                        id: book.book_document.id.id,
+                        index,
+                        ok: false,
+                        aliases: vec![],
+                    }),
+                };
+                entries.extend(entry);
+                books += 1;
+            }
+            "DeleteCitation" => {
+                if let Ok(delete) = quick_xml::de::from_str::<DeleteCitation>(&element.xml) {
+                    entries.push(Entry::Delete(delete.id.iter().map(|id| id.id).collect()));
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(id: u64) -> String {
+        format!("<PubmedArticle><MedlineCitation><PMID>{}</PMID></MedlineCitation></PubmedArticle>", id)
+    }
+
+    #[test]
+    fn scan_keeps_document_order() {
+        let xml = format!(
+            "<PubmedArticleSet>{}{}<DeleteCitation><PMID>1</PMID><PMID>5</PMID></DeleteCitation></PubmedArticleSet>",
+            article(1),
+            article(2)
+        );
+        let entries = scan(xml.as_bytes(), false);
+        assert!(matches!(entries[0], Entry::Article { id: 1, index: 0, ok: false, .. }));
+        assert!(matches!(entries[1], Entry::Article { id: 2, index: 1, ok: false, .. }));
+        assert!(matches!(&entries[2], Entry::Delete(ids) if ids == &vec![1, 5]));
+    }
+
+    #[test]
+    fn scan_keeps_entries_before_malformed_xml() {
+        let xml = format!("<PubmedArticleSet>{}<Bad></Other>{}</PubmedArticleSet>", article(1), article(2));
+        assert!(matches!(scan(xml.as_bytes(), false)[..], [Entry::Article { id: 1, .. }]));
+    }
+
+    #[test]
+    fn crosswalk_keys() {
+        let id = |ty: &str, id: &str| ArticleId { ty: ty.to_string(), id: Some(id.to_string()) };
+        assert_eq!(crosswalk_key(&id("doi", " 10.1016/ABC ")).as_deref(), Some("doi:10.1016/abc"));
+        assert_eq!(crosswalk_key(&id("doi", "https://doi.org/10.1016/abc")).as_deref(), Some("doi:10.1016/abc"));
+        assert_eq!(crosswalk_key(&id("pmc", "PMC123")).as_deref(), Some("pmc:PMC123"));
+        assert_eq!(crosswalk_key(&id("pmc", "123")).as_deref(), Some("pmc:PMC123"));
+        assert_eq!(crosswalk_key(&id("pubmed", "1")), None);
+        assert_eq!(crosswalk_key(&id("pmc", "PMC")), None);
+    }
+
+    fn entry(id: u64, index: u32, ok: bool) -> Entry {
+        Entry::Article { id, index, ok, aliases: vec![] }
+    }
+
+    #[test]
+    fn updates_supersede_and_delete() {
+        let entries = vec![
+            (0, vec![entry(1, 0, true), entry(2, 1, true), entry(3, 2, true), entry(4, 3, true)]),
+            (1, vec![entry(1, 0, true), Entry::Delete(vec![2, 9]), entry(3, 1, false)]),
+        ];
+        let corpus = Corpus::from_entries(entries, true);
+        // a later version wins
+        assert!(corpus.contains(1));
+        assert!(corpus.is_latest(1, 1, 0));
+        assert!(!corpus.is_latest(1, 0, 0));
+        // a deleted PMID is dropped
+        assert!(!corpus.contains(2));
+        assert!(!corpus.is_latest(2, 0, 1));
+        // a broken latest version is not emitted, and neither is the version it supersedes
+        assert!(!corpus.contains(3));
+        assert!(!corpus.is_latest(3, 0, 2));
+        assert!(corpus.is_latest(4, 0, 3));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::de::PubDate;
+use serde::Serialize;
+
+/// how much of [PublicationDate::date] comes from the input, the rest being filled with `01`
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all(serialize = "snake_case"))]
+pub(crate) enum Precision {
+    Day,
+    Month,
+    /// the month approximates the season, i.e. `Spring` is April
+    Season,
+    Year,
+    /// no year could be found, there is no `date`
+    Unknown,
+}
+
+/// a normalized `JournalIssue/PubDate`
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all(serialize = "snake_case"))]
+pub(crate) struct PublicationDate {
+    /// best-effort `YYYY-MM-DD`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) date: Option<String>,
+    pub(crate) precision: Precision,
+    /// the `<MedlineDate>` text, or the structured parts joined by spaces
+    pub(crate) original: String,
+}
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+/// `Jan`, `January` or `1`
+fn parse_month(s: &str) -> Option<u8> {
+    if let Ok(month) = s.parse::<u8>() {
+        return (1..=12).contains(&month).then_some(month);
+    }
+    let s = s.to_ascii_lowercase();
+    MONTHS.iter().position(|m| s.starts_with(m)).map(|i| i as u8 + 1)
+}
+
+fn parse_season(s: &str) -> Option<u8> {
+    match s.to_ascii_lowercase().as_str() {
+        "winter" => Some(1),
+        "spring" => Some(4),
+        "summer" => Some(7),
+        "fall" | "autumn" => Some(10),
+        _ => None,
+    }
+}
+
+fn parse_year(s: &str) -> Option<u16> {
+    (s.len() == 4).then(|| s.parse().ok()).flatten()
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// a day that exists in `month` of `year`, so that `2001 Feb 31` does not become `2001-02-31`
+fn parse_day(s: &str, year: u16, month: u8) -> Option<u8> {
+    s.parse().ok().filter(|day| (1..=days_in_month(year, month)).contains(day))
+}
+
+/// a month or a season, and its precision
+fn parse_month_or_season(s: &str) -> Option<(u8, Precision)> {
+    match (parse_month(s), parse_season(s)) {
+        (Some(month), _) => Some((month, Precision::Month)),
+        (None, Some(month)) => Some((month, Precision::Season)),
+        (None, None) => None,
+    }
+}
+
+/// normalizes `year`, `month` (or season) and `day` tokens, in this order. parsing stops at the first token that does
+/// not fit, so that ranges like `1998 Dec-1999 Jan` keep their start. without a month after the year, the first month
+/// or season before it is taken instead, as in `Summer 1977`.
+fn normalize<'a>(mut tokens: impl Iterator<Item = &'a str>, original: String) -> PublicationDate {
+    let mut before = vec![];
+    let year = loop {
+        match tokens.next() {
+            Some(token) => match parse_year(token) {
+                Some(year) => break year,
+                None => before.push(token),
+            },
+            None => return PublicationDate { date: None, precision: Precision::Unknown, original },
+        }
+    };
+    let (month, day, precision) = match tokens.next().and_then(parse_month_or_season) {
+        Some((month, Precision::Month)) => match tokens.next().and_then(|token| parse_day(token, year, month)) {
+            Some(day) => (month, day, Precision::Day),
+            None => (month, 1, Precision::Month),
+        },
+        Some((month, precision)) => (month, 1, precision),
+        None => match before.into_iter().find_map(parse_month_or_season) {
+            Some((month, precision)) => (month, 1, precision),
+            None => (1, 1, Precision::Year),
+        },
+    };
+    PublicationDate { date: Some(format!("{:04}-{:02}-{:02}", year, month, day)), precision, original }
+}
+
+impl From<&PubDate> for PublicationDate {
+    fn from(value: &PubDate) -> Self {
+        match &value.medline_date {
+            Some(text) => {
+                let tokens = text.split(|c: char| c.is_whitespace() || c == '-' || c == '/').filter(|t| !t.is_empty());
+                normalize(tokens, text.clone())
+            }
+            None => {
+                let parts: Vec<&str> = [&value.year, &value.month, &value.season, &value.day]
+                    .into_iter()
+                    .flatten()
+                    .map(|s| s.trim())
+                    .collect();
+                // seasons never come with a day, so their relative order does not matter
+                normalize(parts.iter().copied(), parts.join(" "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn medline(text: &str) -> PublicationDate {
+        PublicationDate::from(&PubDate { medline_date: Some(text.to_string()), ..Default::default() })
+    }
+
+    #[test]
+    fn structured_dates() {
+        let date = PubDate { year: Some("1975".into()), month: Some("Jun".into()), ..Default::default() };
+        let date = PublicationDate::from(&date);
+        assert_eq!(date.date.as_deref(), Some("1975-06-01"));
+        assert_eq!(date.precision, Precision::Month);
+        assert_eq!(date.original, "1975 Jun");
+
+        let date = PubDate { year: Some("2001".into()), month: Some("11".into()), day: Some("5".into()), ..Default::default() };
+        assert_eq!(PublicationDate::from(&date).date.as_deref(), Some("2001-11-05"));
+
+        let date = PubDate { year: Some("2001".into()), season: Some("Fall".into()), ..Default::default() };
+        assert_eq!(PublicationDate::from(&date).precision, Precision::Season);
+
+        let date = PubDate { year: Some("2001".into()), month: Some("Feb".into()), day: Some("31".into()), ..Default::default() };
+        let date = PublicationDate::from(&date);
+        assert_eq!(date.date.as_deref(), Some("2001-02-01"));
+        assert_eq!(date.precision, Precision::Month);
+
+        let date = PubDate { year: Some("2000".into()), month: Some("Feb".into()), day: Some("29".into()), ..Default::default() };
+        assert_eq!(PublicationDate::from(&date).date.as_deref(), Some("2000-02-29"));
+        let date = PubDate { year: Some("1900".into()), month: Some("Feb".into()), day: Some("29".into()), ..Default::default() };
+        assert_eq!(PublicationDate::from(&date).precision, Precision::Month);
+    }
+
+    #[test]
+    fn medline_dates() {
+        let date = medline("1998 Dec-1999 Jan");
+        assert_eq!(date.date.as_deref(), Some("1998-12-01"));
+        assert_eq!(date.precision, Precision::Month);
+        assert_eq!(date.original, "1998 Dec-1999 Jan");
+
+        assert_eq!(medline("1975-1976").precision, Precision::Year);
+        assert_eq!(medline("2000 Spring-Summer").date.as_deref(), Some("2000-04-01"));
+        assert_eq!(medline("1999 Jul 15-Aug 2").date.as_deref(), Some("1999-07-15"));
+        let date = medline("Summer 1977");
+        assert_eq!(date.date.as_deref(), Some("1977-07-01"));
+        assert_eq!(date.precision, Precision::Season);
+        assert_eq!(medline("Nov-Dec 1977").date.as_deref(), Some("1977-11-01"));
+        assert_eq!(medline("n.d.").precision, Precision::Unknown);
+    }
+}
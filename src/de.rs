@@ -2,11 +2,26 @@ use crate::de::EnumAuthor::{Collective, Person};
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::cmp::PartialEq;
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct PubmedArticleSet {
+    #[serde(default)]
     pub(crate) pubmed_article: Vec<PubmedArticle>,
+    /// NCBI Bookshelf chapters, interleaved with the articles
+    #[serde(default)]
+    pub(crate) pubmed_book_article: Vec<PubmedBookArticle>,
+    /// only in update files. PMIDs removed from PubMed since the previous files
+    #[serde(default)]
+    pub(crate) delete_citation: Vec<DeleteCitation>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct DeleteCitation {
+    #[serde(rename(deserialize = "PMID"), default)]
+    pub(crate) id: Vec<PMID>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -16,14 +31,111 @@ pub(crate) struct PubmedArticle {
     pub(crate) pubmed_data: PubMedData,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct PubmedBookArticle {
+    pub(crate) book_document: BookDocument,
+    pub(crate) pubmed_book_data: PubmedBookData,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct BookDocument {
+    #[serde(rename(deserialize = "PMID"))]
+    pub(crate) id: PMID,
+    pub(crate) book: Book,
+    /// the chapter title. absent when the document is the whole book
+    #[serde(deserialize_with = "join_segmented_string", default)]
+    pub(crate) article_title: String,
+    /// both `Type="authors"` and `Type="editors"` lists may appear
+    #[serde(default)]
+    pub(crate) author_list: Vec<AuthorList>,
+    #[serde(default)]
+    pub(crate) publication_type: Vec<PublicationType>,
+    pub(crate) r#abstract: Option<Abstract>,
+    pub(crate) sections: Option<Sections>,
+    #[serde(default)]
+    pub(crate) keyword_list: Vec<KeywordList>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Book {
+    pub(crate) publisher: Publisher,
+    #[serde(deserialize_with = "join_segmented_string")]
+    pub(crate) book_title: String,
+    /// usually the editors
+    #[serde(default)]
+    pub(crate) author_list: Vec<AuthorList>,
+    #[serde(default)]
+    pub(crate) isbn: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Publisher {
+    pub(crate) publisher_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) publisher_location: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Sections {
+    #[serde(default)]
+    pub(crate) section: Vec<Section>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Section {
+    #[serde(deserialize_with = "join_segmented_string", rename(serialize = "title"), default)]
+    pub(crate) section_title: String,
+    /// sections may nest
+    #[serde(skip_serializing_if = "Vec::is_empty", rename(serialize = "sections"), default)]
+    pub(crate) section: Vec<Section>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct PubmedBookData {
+    pub(crate) article_id_list: ArticleIdList,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct PubMedData {
+    pub(crate) history: Option<History>,
+    /// `ppublish`, `epublish` or `aheadofprint`
+    pub(crate) publication_status: Option<String>,
     pub(crate) article_id_list: ArticleIdList,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub(crate) reference_list: Vec<ReferenceList>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct History {
+    #[serde(default)]
+    pub(crate) pub_med_pub_date: Vec<PubMedPubDate>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct PubMedPubDate {
+    /// `received`, `accepted`, `revised`, `entrez`, `pubmed`, `medline`...
+    #[serde(rename(deserialize = "@PubStatus"))]
+    pub(crate) status: String,
+    year: u16,
+    month: u8,
+    day: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hour: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minute: Option<u8>,
+}
+
+#[allow(dead_code)]
 fn pubmed_reference_list_deser<'de, D>(deserializer: D) -> Result<Option<ReferenceList>, D::Error>
 where
     D: Deserializer<'de>,
@@ -45,7 +157,7 @@ where
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct Reference {
-    #[serde(deserialize_with = "join_segmented_string")]
+    #[serde(deserialize_with = "join_inline_string")]
     pub(crate) citation: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) article_id_list: Option<ArticleIdList>,
@@ -54,9 +166,13 @@ pub(crate) struct Reference {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct ReferenceList {
-    // TODO some ReferenceList may contains another ReferenceList. here we just ignore them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
     #[serde(default)]
     pub(crate) reference: Vec<Reference>,
+    /// sections of the list, i.e. references of the supplementary material
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub(crate) reference_list: Vec<ReferenceList>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -70,6 +186,12 @@ pub(crate) struct MedlineCitation {
     pub(crate) medline_journal_info: MedlineJournalInfo,
     #[serde(default)]
     pub(crate) keyword_list: Vec<KeywordList>,
+    pub(crate) mesh_heading_list: Option<MeshHeadingList>,
+    pub(crate) chemical_list: Option<ChemicalList>,
+    pub(crate) suppl_mesh_list: Option<SupplMeshList>,
+    pub(crate) comments_corrections_list: Option<CommentsCorrectionsList>,
+    #[serde(default)]
+    pub(crate) other_abstract: Vec<OtherAbstract>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -79,17 +201,38 @@ pub(crate) struct MedlineJournalInfo {
     pub(crate) id: String,
     #[serde(deserialize_with = "unwrap_string")]
     pub(crate) country: String,
-    #[serde(deserialize_with = "unwrap_string", rename(deserialize = "ISSNLinking"), default)]
-    pub(crate) issn: String,
+    /// the NLM title abbreviation, i.e. `Biochem Med`
+    #[serde(rename(deserialize = "MedlineTA"))]
+    pub(crate) medline_ta: Option<String>,
+    /// the ISSN that links the print and electronic versions of the journal
+    #[serde(rename(deserialize = "ISSNLinking"))]
+    pub(crate) issn_linking: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct JournalIssue {
+    /// `Print` or `Internet`
+    #[serde(rename(deserialize = "@CitedMedium"), skip_serializing_if = "Option::is_none")]
+    pub(crate) cited_medium: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) volume: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) issue: Option<String>,
+    /// normalized into [crate::date::PublicationDate]
+    #[serde(skip_serializing)]
+    pub(crate) pub_date: Option<PubDate>,
+}
+
+/// either structured, with a year at least, or a free-text `<MedlineDate>`
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all(deserialize = "PascalCase"))]
+pub(crate) struct PubDate {
+    pub(crate) year: Option<String>,
+    pub(crate) month: Option<String>,
+    pub(crate) day: Option<String>,
+    pub(crate) season: Option<String>,
+    pub(crate) medline_date: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -99,8 +242,8 @@ pub(crate) struct Journal {
     pub(crate) issn: Option<ISSN>,
     pub(crate) journal_issue: Option<JournalIssue>,
     pub(crate) title: String,
-    // #[serde(rename(deserialize = "ISOAbbreviation"))]
-    // pub(crate) iso_abbreviation: String,
+    #[serde(rename(deserialize = "ISOAbbreviation"))]
+    pub(crate) iso_abbreviation: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -113,12 +256,25 @@ pub(crate) struct Article {
     #[serde(deserialize_with = "join_segmented_string")]
     pub(crate) article_title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pagination: Option<Pagination>,
+    #[serde(rename(deserialize = "ELocationID"), skip_serializing_if = "Vec::is_empty", default)]
+    pub(crate) elocation_id: Vec<ELocationID>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) r#abstract: Option<Abstract>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) author_list: Option<AuthorList>,
     pub(crate) publication_type_list: PublicationTypeList,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) grant_list: Option<GrantList>,
+    /// ISO 639-2 codes, i.e. `eng`. repeated for multilingual articles
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub(crate) language: Vec<String>,
+    /// the original title of a non-English article, whose article_title is the English translation
+    #[serde(deserialize_with = "join_segmented_string", skip_serializing_if = "String::is_empty", default)]
+    pub(crate) vernacular_title: String,
 }
 
+#[allow(dead_code)]
 fn raw_de<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -127,45 +283,42 @@ where
     str.or(Ok("Corrupted Article Title".to_string()))
 }
 
-fn join_segmented_string<'de, D>(deserializer: D) -> Result<String, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    // this is SO STUPID. but I really couldn't find a way out
-    // https://github.com/tafia/quick-xml/issues/257#issuecomment-2402816555
-    // basically being toyed by Rust ecosystem, YET AGAIN
-    #[derive(Deserialize, Debug)]
-    #[serde(rename_all(deserialize = "snake_case"))]
-    enum ItalicBoldString {
-        Sup,
-        Sub,
-        I(String),
-        B(ItalicBoldStringWrapper),
-        #[serde(rename = "$text")]
-        String(String),
-    }
-    #[derive(Deserialize, Debug)]
-    struct ItalicBoldStringWrapper {
-        #[serde(rename(deserialize = "$value"), default)]
-        field: Vec<ItalicBoldString>,
-    }
-    #[derive(Deserialize, Debug)]
-    #[serde(rename_all(deserialize = "snake_case"))]
-    enum CouldBeString {
-        Sup(ItalicBoldStringWrapper),
-        Sub(ItalicBoldStringWrapper),
-        I(ItalicBoldStringWrapper),
-        B(ItalicBoldStringWrapper),
-        Math,
-        #[serde(rename = "$text")]
-        String(String),
-    }
-    #[derive(Deserialize, Debug)]
-    struct SegmentedString {
-        #[serde(rename(deserialize = "$value"), default)]
-        field: Vec<CouldBeString>,
-    }
+// this is SO STUPID. but I really couldn't find a way out
+// https://github.com/tafia/quick-xml/issues/257#issuecomment-2402816555
+// basically being toyed by Rust ecosystem, YET AGAIN
+#[derive(Deserialize, Debug)]
+#[serde(rename_all(deserialize = "snake_case"))]
+enum ItalicBoldString {
+    Sup,
+    Sub,
+    I(String),
+    B(ItalicBoldStringWrapper),
+    #[serde(rename = "$text")]
+    String(String),
+}
+#[derive(Deserialize, Debug)]
+struct ItalicBoldStringWrapper {
+    #[serde(rename(deserialize = "$value"), default)]
+    field: Vec<ItalicBoldString>,
+}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all(deserialize = "snake_case"))]
+enum CouldBeString {
+    Sup(ItalicBoldStringWrapper),
+    Sub(ItalicBoldStringWrapper),
+    I(ItalicBoldStringWrapper),
+    B(ItalicBoldStringWrapper),
+    Math,
+    #[serde(rename = "$text")]
+    String(String),
+}
+#[derive(Deserialize, Debug)]
+struct SegmentedString {
+    #[serde(rename(deserialize = "$value"), default)]
+    field: Vec<CouldBeString>,
+}
 
+fn segment_text(segment: &CouldBeString) -> String {
     fn traverse_ibs_wrapper(ibs: &ItalicBoldStringWrapper) -> String {
         ibs.field.iter()
             .map(|e| match &e {
@@ -176,22 +329,147 @@ where
             }).collect()
     }
 
-    Ok(SegmentedString::deserialize(deserializer)?.field.iter()
-        .map(|e| match e {
-            CouldBeString::I(str) => traverse_ibs_wrapper(&str),
-            CouldBeString::B(str) => traverse_ibs_wrapper(&str),
-            CouldBeString::Sup(str) => traverse_ibs_wrapper(&str),
-            CouldBeString::Sub(str) => traverse_ibs_wrapper(&str),
-            CouldBeString::String(str) => str.clone(),
-            &CouldBeString::Math => "".to_string(),
-        })
-        .map(|e| e.trim().to_string())
-        .collect::<Vec<_>>().join(" "))
+    match segment {
+        CouldBeString::I(str) => traverse_ibs_wrapper(str),
+        CouldBeString::B(str) => traverse_ibs_wrapper(str),
+        CouldBeString::Sup(str) => traverse_ibs_wrapper(str),
+        CouldBeString::Sub(str) => traverse_ibs_wrapper(str),
+        CouldBeString::String(str) => str.clone(),
+        &CouldBeString::Math => "".to_string(),
+    }
 }
 
+fn join_segments(segments: &[CouldBeString]) -> String {
+    segments.iter()
+        .map(|e| segment_text(e).trim().to_string())
+        .collect::<Vec<_>>().join(" ")
+}
+
+/// like [join_segments], but for running text with inline markup. quick-xml trims the text around each tag, so the
+/// spaces are put back between words only, not before punctuation or a `<sup>`/`<sub>`, nor inside brackets and
+/// hyphenated words: `It <i>works</i> (<i>p</i> &lt; 0.05).` is `It works (p < 0.05).`
+fn join_inline_segments(segments: &[CouldBeString]) -> String {
+    let mut res = String::new();
+    for segment in segments {
+        let text = segment_text(segment);
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let attached = matches!(segment, CouldBeString::Sup(_) | CouldBeString::Sub(_))
+            || text.starts_with(['.', ',', ';', ':', '!', '?', ')', ']', '}', '%', '-', '/'])
+            || res.ends_with(['(', '[', '{', '-', '/']);
+        if !res.is_empty() && !attached {
+            res.push(' ');
+        }
+        res.push_str(text);
+    }
+    res
+}
+
+fn join_segmented_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(join_segments(&SegmentedString::deserialize(deserializer)?.field))
+}
+
+/// like [join_segmented_string], but for a `$value` field next to attributes of the same element
+fn join_segmented_value<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(join_segments(&Vec::<CouldBeString>::deserialize(deserializer)?))
+}
+
+/// like [join_segmented_string], with [join_inline_segments]
+fn join_inline_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(join_inline_segments(&SegmentedString::deserialize(deserializer)?.field))
+}
+
+/// like [join_segmented_value], with [join_inline_segments]
+fn join_inline_value<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(join_inline_segments(&Vec::<CouldBeString>::deserialize(deserializer)?))
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn abstract_text_keeps_attributes() {
+        let xml = r#"
+        <Abstract>
+            <AbstractText Label="RESULTS" NlmCategory="RESULTS">It <i>works</i> (<i>p</i> &lt; 0.05).</AbstractText>
+            <AbstractText>Tested <i>in vitro</i>, with CO<sub>2</sub> and <i>BRCA1</i>-mutated cells.</AbstractText>
+            <CopyrightInformation>Copyright 2024.</CopyrightInformation>
+        </Abstract>
+        "#;
+        let res: Abstract = quick_xml::de::from_str(xml).unwrap();
+        assert_eq!(res.abstract_text[0].label.as_deref(), Some("RESULTS"));
+        assert_eq!(res.abstract_text[0].nlm_category.as_deref(), Some("RESULTS"));
+        assert_eq!(res.abstract_text[0].text, "It works (p < 0.05).");
+        assert_eq!(res.abstract_text[1].label, None);
+        assert_eq!(res.abstract_text[1].text, "Tested in vitro, with CO2 and BRCA1-mutated cells.");
+        assert_eq!(res.copyright_information.as_deref(), Some("Copyright 2024."));
+    }
+
+    #[test]
+    fn mesh_heading_flags() {
+        let xml = r#"
+        <MeshHeading>
+            <DescriptorName UI="D000445" MajorTopicYN="N">Aldehyde Oxidoreductases</DescriptorName>
+            <QualifierName UI="Q000378" MajorTopicYN="Y">metabolism</QualifierName>
+            <QualifierName UI="Q000097">blood</QualifierName>
+        </MeshHeading>
+        "#;
+        let res: MeshHeading = quick_xml::de::from_str(xml).unwrap();
+        assert_eq!(res.descriptor_name.id, "D000445");
+        assert!(!res.descriptor_name.is_major);
+        assert_eq!(res.qualifier_name.len(), 2);
+        assert!(res.qualifier_name[0].is_major);
+        assert!(!res.qualifier_name[1].is_major);
+        assert_eq!(res.qualifier_name[1].name, "blood");
+    }
+
+    #[test]
+    fn author_identifiers_and_affiliations() {
+        let xml = r#"
+        <AuthorList>
+            <Author ValidYN="Y" EqualContrib="Y">
+                <LastName>Makar</LastName><ForeName>A B</ForeName>
+                <Identifier Source="ORCID">0000-0001-2345-6789</Identifier>
+                <AffiliationInfo><Affiliation>Univ A</Affiliation><Identifier Source="ROR">https://ror.org/000</Identifier></AffiliationInfo>
+            </Author>
+            <Author ValidYN="N"><CollectiveName>Study Group</CollectiveName></Author>
+        </AuthorList>
+        "#;
+        let res: AuthorList = quick_xml::de::from_str(xml).unwrap();
+        let json = serde_json::to_string(&res.author).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"last_name":"Makar","fore_name":"A B","identifiers":{"ORCID":"0000-0001-2345-6789"},"affiliation":[{"name":"Univ A","identifiers":{"ROR":"https://ror.org/000"}}],"valid":true,"equal_contrib":true},{"collective_name":"Study Group","valid":false}]"#
+        );
+    }
+
+    #[test]
+    fn keyword_attributes() {
+        let xml = r#"<KeywordList Owner="NOTNLM"><Keyword MajorTopicYN="N">formate</Keyword><Keyword MajorTopicYN="Y">methanol <i>poisoning</i></Keyword></KeywordList>"#;
+        let res: KeywordList = quick_xml::de::from_str(xml).unwrap();
+        let keywords: Vec<_> = res.keywords().collect();
+        assert_eq!(keywords[0].name, "formate");
+        assert!(!keywords[0].is_major);
+        assert_eq!(keywords[1].name, "methanol poisoning");
+        assert!(keywords[1].is_major);
+        assert_eq!(keywords[1].owner.as_deref(), Some("NOTNLM"));
+    }
+
     #[test]
     fn join_segmented_string_test() {
         let xml = r"
@@ -209,10 +487,73 @@ mod tests {
 
         let xd = &mut quick_xml::de::Deserializer::from_str(xml);
         let res: Result<AnyName, _> = serde_path_to_error::deserialize(xd);
-        println!("{:#?}", res.unwrap());
+        assert_eq!(res.unwrap().title, "text 1-y");
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Abstract {
+    /// a structured abstract has one AbstractText per section
+    #[serde(default)]
+    pub(crate) abstract_text: Vec<AbstractText>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) copyright_information: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Pagination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) start_page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) end_page: Option<String>,
+    /// i.e. `117-26`, with the common leading digits of the end page left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) medline_pgn: Option<String>,
+}
+
+/// an electronic location, for articles without pages or in addition to them
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) struct ELocationID {
+    /// `doi` or `pii`
+    #[serde(rename(deserialize = "@EIdType"))]
+    pub(crate) ty: String,
+    #[serde(rename(deserialize = "@ValidYN"), deserialize_with = "unwrap_yn", default = "yes")]
+    pub(crate) valid: bool,
+    #[serde(rename(deserialize = "$value"))]
+    pub(crate) id: String,
+}
+
+/// an abstract provided by someone other than the publisher, often in another language
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct OtherAbstract {
+    /// `Publisher`, `NASA`, `KIE`, `PIP`...
+    #[serde(rename(deserialize = "@Type"))]
+    pub(crate) ty: String,
+    #[serde(rename(deserialize = "@Language"), skip_serializing_if = "Option::is_none")]
+    pub(crate) language: Option<String>,
+    #[serde(default)]
+    pub(crate) abstract_text: Vec<AbstractText>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) copyright_information: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct AbstractText {
+    #[serde(rename(deserialize = "@Label"), skip_serializing_if = "Option::is_none")]
+    pub(crate) label: Option<String>,
+    /// BACKGROUND, OBJECTIVE, METHODS, RESULTS, CONCLUSIONS or UNASSIGNED
+    #[serde(rename(deserialize = "@NlmCategory"), skip_serializing_if = "Option::is_none")]
+    pub(crate) nlm_category: Option<String>,
+    #[serde(rename(deserialize = "$value"), deserialize_with = "join_inline_value", default)]
+    pub(crate) text: String,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct Grant {
@@ -234,13 +575,14 @@ pub(crate) struct GrantList {
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
-#[serde(transparent)]
 pub(crate) struct Keyword {
-    #[serde(deserialize_with = "join_segmented_string")]
+    #[serde(rename(deserialize = "$value"), deserialize_with = "join_segmented_value", default)]
     pub(crate) name: String,
-    // TODO: save is major?
-    // #[serde(rename(deserialize = "@MajorTopicYN"), deserialize_with = "unwrap_yn")]
-    // pub(crate) is_major: bool,
+    #[serde(rename(deserialize = "@MajorTopicYN"), deserialize_with = "unwrap_yn", default)]
+    pub(crate) is_major: bool,
+    /// copied from the KeywordList
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub(crate) owner: Option<String>,
 }
 
 fn unwrap_yn<'de, D>(deserializer: D) -> Result<bool, D::Error>
@@ -253,10 +595,111 @@ where
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct KeywordList {
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    /// `NOTNLM` for author keywords, `NLM`, `PIP`, `KIE` or `NASA`
+    #[serde(rename(deserialize = "@Owner"), skip_serializing_if = "Option::is_none")]
+    pub(crate) owner: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub(crate) keyword: Vec<Keyword>,
 }
 
+impl KeywordList {
+    /// the keywords, each with the owner of the list
+    pub(crate) fn keywords(&self) -> impl Iterator<Item = Keyword> + '_ {
+        self.keyword.iter().map(|k| Keyword { owner: self.owner.clone(), ..k.clone() })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct MeshHeadingList {
+    #[serde(default)]
+    pub(crate) mesh_heading: Vec<MeshHeading>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct MeshHeading {
+    #[serde(rename(serialize = "descriptor"))]
+    pub(crate) descriptor_name: MeshTerm,
+    #[serde(rename(serialize = "qualifiers"), skip_serializing_if = "Vec::is_empty", default)]
+    pub(crate) qualifier_name: Vec<MeshTerm>,
+}
+
+/// a MeSH descriptor or qualifier
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct MeshTerm {
+    #[serde(rename(deserialize = "@UI"))]
+    pub(crate) id: String,
+    #[serde(rename(deserialize = "@MajorTopicYN"), deserialize_with = "unwrap_yn", default)]
+    pub(crate) is_major: bool,
+    #[serde(rename(deserialize = "$value"))]
+    pub(crate) name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct ChemicalList {
+    #[serde(default)]
+    pub(crate) chemical: Vec<Chemical>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Chemical {
+    /// CAS registry number or EC number, `0` when there is none
+    pub(crate) registry_number: String,
+    #[serde(rename(serialize = "substance"))]
+    pub(crate) name_of_substance: Substance,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Substance {
+    #[serde(rename(deserialize = "@UI"))]
+    pub(crate) id: String,
+    #[serde(rename(deserialize = "$value"))]
+    pub(crate) name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct SupplMeshList {
+    #[serde(default)]
+    pub(crate) suppl_mesh_name: Vec<SupplMeshName>,
+}
+
+/// a supplementary concept record
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct SupplMeshName {
+    /// `Disease`, `Protocol` or `Organism`
+    #[serde(rename(deserialize = "@Type"))]
+    pub(crate) ty: String,
+    #[serde(rename(deserialize = "@UI"))]
+    pub(crate) id: String,
+    #[serde(rename(deserialize = "$value"))]
+    pub(crate) name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct CommentsCorrectionsList {
+    #[serde(default)]
+    pub(crate) comments_corrections: Vec<CommentsCorrections>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct CommentsCorrections {
+    /// `ErratumIn`, `RetractionIn`, `CommentOn`, `UpdateOf`...
+    #[serde(rename(deserialize = "@RefType"))]
+    pub(crate) ty: String,
+    pub(crate) ref_source: String,
+    #[serde(rename(deserialize = "PMID"))]
+    pub(crate) id: Option<PMID>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
 pub(crate) struct ArticleId {
@@ -299,14 +742,53 @@ pub(crate) enum EnumAuthor {
         fore_name: String,
         #[serde(skip_serializing_if = "String::is_empty")]
         initials: String,
+        /// i.e. `{"ORCID": "0000-0001-2345-6789"}`
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        identifiers: BTreeMap<String, String>,
         #[serde(skip_serializing_if = "Vec::is_empty")]
-        affiliation: Vec<String>,
+        affiliation: Vec<Affiliation>,
+        /// false when the name is known to be misspelled in the publication
+        valid: bool,
+        #[serde(skip_serializing_if = "is_false")]
+        equal_contrib: bool,
     },
     Collective {
         collective_name: String,
+        valid: bool,
+        #[serde(skip_serializing_if = "is_false")]
+        equal_contrib: bool,
     },
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+pub(crate) struct Affiliation {
+    pub(crate) name: String,
+    /// ROR, GRID or ISNI ids of the institution
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) identifiers: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Identifier {
+    #[serde(rename(deserialize = "@Source"))]
+    source: String,
+    #[serde(rename(deserialize = "$value"), default)]
+    id: String,
+}
+
+fn identifiers(lst: &[Identifier]) -> BTreeMap<String, String> {
+    lst.iter().map(|i| (i.source.clone(), i.id.trim().to_string())).collect()
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+fn yes() -> bool {
+    true
+}
+
 #[derive(Deserialize)]
 pub(crate) struct StringValueUnwrapper {
     #[serde(rename(deserialize = "$value"))]
@@ -322,11 +804,17 @@ where
     struct FlatAuthorAffiliation {
         #[serde(deserialize_with = "unwrap_string", default)]
         affiliation: String,
+        #[serde(default)]
+        identifier: Vec<Identifier>,
     }
 
     #[derive(Deserialize, Debug)]
     #[serde(rename_all(deserialize = "PascalCase"))]
     struct FlatAuthor {
+        #[serde(rename(deserialize = "@ValidYN"), deserialize_with = "unwrap_yn", default = "yes")]
+        valid: bool,
+        #[serde(rename(deserialize = "@EqualContrib"), deserialize_with = "unwrap_yn", default)]
+        equal_contrib: bool,
         #[serde(deserialize_with = "unwrap_string", default)]
         last_name: String,
         #[serde(deserialize_with = "unwrap_string", default)]
@@ -336,6 +824,8 @@ where
         #[serde(deserialize_with = "unwrap_string", default)]
         collective_name: String,
         #[serde(default)]
+        identifier: Vec<Identifier>,
+        #[serde(default)]
         affiliation_info: Vec<FlatAuthorAffiliation>,
     }
     let lst: Vec<FlatAuthor> = Deserialize::deserialize(deserializer)?;
@@ -350,11 +840,18 @@ where
                         last_name: de.last_name.clone(),
                         fore_name: de.fore_name.clone(),
                         initials: de.initials.clone(),
-                        affiliation: de.affiliation_info.iter().map(|de| de.affiliation.clone()).collect(),
+                        identifiers: identifiers(&de.identifier),
+                        affiliation: de.affiliation_info.iter()
+                            .map(|de| Affiliation { name: de.affiliation.clone(), identifiers: identifiers(&de.identifier) })
+                            .collect(),
+                        valid: de.valid,
+                        equal_contrib: de.equal_contrib,
                     })
                 } else {
                     Ok(Collective {
-                        collective_name: de.collective_name.clone()
+                        collective_name: de.collective_name.clone(),
+                        valid: de.valid,
+                        equal_contrib: de.equal_contrib,
                     })
                 }
             } else {
@@ -373,12 +870,16 @@ where
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub(crate) struct AuthorList {
+    /// `authors` or `editors`
+    #[serde(rename(deserialize = "@Type"), skip_serializing, default)]
+    pub(crate) ty: Option<String>,
     #[serde(rename(deserialize = "Author"), deserialize_with = "de_vec_enum_author")]
     pub(crate) author: Vec<EnumAuthor>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+#[allow(clippy::upper_case_acronyms)]
 pub(crate) struct ISSN {
     #[serde(rename(deserialize = "$value"))]
     pub(crate) id: String,
@@ -388,6 +889,7 @@ pub(crate) struct ISSN {
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase", serialize = "snake_case"))]
+#[allow(clippy::upper_case_acronyms)]
 pub(crate) struct PMID {
     #[serde(rename(deserialize = "$value"))]
     pub(crate) id: u64,
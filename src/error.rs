@@ -0,0 +1,104 @@
+use crate::verify::VerifyError;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub(crate) enum ErrorKind {
+    Io(io::Error),
+    Xml(quick_xml::Error),
+    De(quick_xml::DeError),
+    Json(serde_json::Error),
+    Verify(VerifyError),
+}
+
+/// an error while processing a single input file. `file` is attached by the caller, while `path` and `offset`
+/// locate the failure inside the document when they are known.
+#[derive(Debug)]
+pub(crate) struct Error {
+    pub(crate) file: Option<PathBuf>,
+    /// serde path of the failing element, i.e. `PubmedArticle[12].MedlineCitation.Article.AuthorList.Author`
+    pub(crate) path: Option<String>,
+    /// byte offset into the (decompressed) input
+    pub(crate) offset: Option<u64>,
+    pub(crate) kind: Box<ErrorKind>,
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn in_file(mut self, file: &Path) -> Self {
+        self.file = Some(file.to_path_buf());
+        self
+    }
+
+    pub(crate) fn at_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub(crate) fn with_path(mut self, path: String) -> Self {
+        self.path = Some(path);
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match self.kind.as_ref() {
+            ErrorKind::Io(e) => write!(f, "{}", e)?,
+            ErrorKind::Xml(e) => write!(f, "malformed XML: {}", e)?,
+            ErrorKind::De(e) => write!(f, "{}", e)?,
+            ErrorKind::Json(e) => write!(f, "failed to serialize: {}", e)?,
+            ErrorKind::Verify(e) => write!(f, "{}", e)?,
+        }
+        if let Some(path) = &self.path {
+            write!(f, " at {}", path)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " (byte {})", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error { file: None, path: None, offset: None, kind: Box::new(kind) }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        ErrorKind::Io(e).into()
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        ErrorKind::Xml(e).into()
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        ErrorKind::Json(e).into()
+    }
+}
+
+impl From<VerifyError> for Error {
+    fn from(e: VerifyError) -> Self {
+        ErrorKind::Verify(e).into()
+    }
+}
+
+/// keeps the path collected by [serde_path_to_error], which would otherwise be lost
+impl From<serde_path_to_error::Error<quick_xml::DeError>> for Error {
+    fn from(e: serde_path_to_error::Error<quick_xml::DeError>) -> Self {
+        let path = e.path().to_string();
+        Error::from(ErrorKind::De(e.into_inner())).with_path(path)
+    }
+}
@@ -0,0 +1,94 @@
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// opens a baseline file for reading. gzip-compressed files (i.e. `pubmed24n0001.xml.gz`) are detected by their
+/// magic bytes and decompressed on the fly.
+pub(crate) fn open_input(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+pub(crate) enum OutputFile {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl OutputFile {
+    pub(crate) fn create(path: &Path, gzip: bool) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(if gzip {
+            OutputFile::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            OutputFile::Plain(file)
+        })
+    }
+
+    /// flushes the file, writing the gzip trailer if needed
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self {
+            OutputFile::Plain(mut w) => w.flush(),
+            OutputFile::Gzip(w) => w.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            OutputFile::Plain(w) => w.write(buf),
+            OutputFile::Gzip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OutputFile::Plain(w) => w.flush(),
+            OutputFile::Gzip(w) => w.flush(),
+        }
+    }
+}
+
+/// file name without the `.xml` / `.xml.gz` extension, i.e. `pubmed24n0001`
+pub(crate) fn base_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    name.strip_suffix(".xml").unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn base_name_strips_extensions() {
+        assert_eq!(base_name(Path::new("dir/pubmed24n0001.xml.gz")), "pubmed24n0001");
+        assert_eq!(base_name(Path::new("dir/pubmed24n0001.xml")), "pubmed24n0001");
+        assert_eq!(base_name(Path::new("pubmed24n0001")), "pubmed24n0001");
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        let dir = std::env::temp_dir().join(format!("cs307-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.xml.gz");
+        let mut out = OutputFile::create(&path, true).unwrap();
+        out.write_all(b"<PubmedArticleSet/>").unwrap();
+        out.finish().unwrap();
+
+        let mut content = String::new();
+        open_input(&path).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "<PubmedArticleSet/>");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use crate::cli::OutputFormat;
+use crate::files::OutputFile;
+use crate::output::{ConflictReport, RecordWriter};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// a row of `journals.ndjson`, which articles reference by `id`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all(serialize = "snake_case"))]
+pub(crate) struct Journal {
+    /// from MedlineJournalInfo
+    pub(crate) id: String,
+    pub(crate) country: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) medline_ta: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) issn_linking: Option<String>,
+    /// from Journal
+    pub(crate) title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) iso_abbreviation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) issn_print: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) issn_electronic: Option<String>,
+}
+
+/// a field of a journal that differs between two articles. the first value seen is kept
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Conflict {
+    pub(crate) journal: String,
+    pub(crate) field: &'static str,
+    pub(crate) kept: String,
+    pub(crate) other: String,
+}
+
+/// distinct journals in order of first appearance
+#[derive(Debug, Default)]
+pub(crate) struct Journals {
+    journals: Vec<Journal>,
+    index: HashMap<String, usize>,
+    conflicts: Vec<Conflict>,
+    seen: HashSet<Conflict>,
+}
+
+impl Journals {
+    /// records `journal`, filling in the fields that were missing so far
+    pub(crate) fn add(&mut self, journal: &Journal) {
+        let Some(&index) = self.index.get(&journal.id) else {
+            self.index.insert(journal.id.clone(), self.journals.len());
+            self.journals.push(journal.clone());
+            return;
+        };
+        let kept = &mut self.journals[index];
+        let mut conflicts = vec![];
+        let mut merge = |field: &'static str, kept: &mut Option<String>, other: &Option<String>| match (&kept, other) {
+            (None, Some(_)) => kept.clone_from(other),
+            (Some(a), Some(b)) if a != b => conflicts.push((field, a.clone(), b.clone())),
+            _ => {}
+        };
+        let (mut title, mut country) = (Some(kept.title.clone()), Some(kept.country.clone()));
+        merge("title", &mut title, &Some(journal.title.clone()));
+        merge("country", &mut country, &Some(journal.country.clone()));
+        merge("medline_ta", &mut kept.medline_ta, &journal.medline_ta);
+        merge("issn_linking", &mut kept.issn_linking, &journal.issn_linking);
+        merge("iso_abbreviation", &mut kept.iso_abbreviation, &journal.iso_abbreviation);
+        merge("issn_print", &mut kept.issn_print, &journal.issn_print);
+        merge("issn_electronic", &mut kept.issn_electronic, &journal.issn_electronic);
+        for (field, a, b) in conflicts {
+            self.conflict(Conflict { journal: journal.id.clone(), field, kept: a, other: b });
+        }
+    }
+
+    /// each distinct conflict is only reported once
+    fn conflict(&mut self, conflict: Conflict) {
+        if self.seen.insert(conflict.clone()) {
+            self.conflicts.push(conflict);
+        }
+    }
+
+    /// `other`, from a later job, into `self`
+    fn merge(&mut self, other: Journals) {
+        for conflict in other.conflicts {
+            self.conflict(conflict);
+        }
+        for journal in &other.journals {
+            self.add(journal);
+        }
+    }
+}
+
+/// journals of every successful job, with the job index
+pub(crate) static JOURNALS: Mutex<Vec<(usize, Journals)>> = Mutex::new(vec![]);
+
+/// merges the journals of every job, in job order so that the first value seen is always the same, writes them to
+/// `journals` and their conflicts to `conflicts`. returns the number of journals and conflicts written.
+pub(crate) fn write_journals(
+    mut jobs: Vec<(usize, Journals)>,
+    journals: &Path,
+    conflicts: &Path,
+    format: OutputFormat,
+    gzip: bool,
+) -> io::Result<(usize, usize)> {
+    jobs.sort_by_key(|(index, _)| *index);
+    let mut all = Journals::default();
+    for (_, job) in jobs {
+        all.merge(job);
+    }
+    let mut writer = RecordWriter::new(OutputFile::create(journals, gzip)?, format);
+    for journal in &all.journals {
+        writer.write_record(&serde_json::to_string(journal)?)?;
+    }
+    writer.finish()?.finish()?;
+    let mut report = ConflictReport::new(conflicts);
+    for conflict in &all.conflicts {
+        report.report(conflict)?;
+    }
+    report.finish()?;
+    Ok((all.journals.len(), all.conflicts.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(id: &str, title: &str, issn_print: Option<&str>) -> Journal {
+        Journal {
+            id: id.to_string(),
+            country: "United States".to_string(),
+            medline_ta: None,
+            issn_linking: None,
+            title: title.to_string(),
+            iso_abbreviation: None,
+            issn_print: issn_print.map(str::to_string),
+            issn_electronic: None,
+        }
+    }
+
+    #[test]
+    fn merge_fills_and_reports() {
+        let mut first = Journals::default();
+        first.add(&journal("1", "Biochem Med", None));
+        first.add(&journal("2", "Other", None));
+        let mut second = Journals::default();
+        second.add(&journal("1", "Biochemical Medicine", Some("0006-2944")));
+        second.add(&journal("1", "Biochemical Medicine", None));
+        first.merge(second);
+
+        assert_eq!(first.journals.len(), 2);
+        assert_eq!(first.journals[0].title, "Biochem Med");
+        assert_eq!(first.journals[0].issn_print.as_deref(), Some("0006-2944"));
+        assert_eq!(
+            first.conflicts,
+            vec![Conflict {
+                journal: "1".to_string(),
+                field: "title",
+                kept: "Biochem Med".to_string(),
+                other: "Biochemical Medicine".to_string(),
+            }]
+        );
+    }
+}
@@ -1,46 +1,282 @@
+mod authors;
+mod cli;
+mod corpus;
+mod date;
 mod de;
+mod error;
+mod files;
+mod journals;
+mod output;
 mod ser;
 mod stats;
+mod stream;
+mod verify;
 
 use rayon::prelude::*;
 
-use crate::ser::run_de_ser;
+use crate::authors::{write_tables, AuthorLinks, AUTHORS};
+use crate::cli::{Cli, Job, RefCutoff};
+use crate::corpus::{Corpus, CORPUS};
+use crate::de::EnumAuthor;
+use crate::error::{Error, Result};
+use crate::files::{open_input, OutputFile};
+use crate::journals::{write_journals, Journal, Journals, JOURNALS};
+use crate::output::{DanglingReport, Quarantine, RecordWriter};
+use crate::ser::{run_de_ser, run_de_ser_stream, Sink};
 use crate::stats::STATS;
+use crate::stream::RawElement;
+use crate::verify::{verify_md5, VerifyError};
+use clap::Parser;
 use std::fs;
-use std::io::{LineWriter, Write};
+use std::io::{self, Read};
+use std::process::ExitCode;
 use std::sync::atomic::Ordering;
 
-fn do_deser(prefix: String) {
-    let xml_path = format!("{}.xml", prefix);
-    let content = fs::read_to_string(&xml_path).unwrap();
-    let deser = run_de_ser(&content);
+/// everything written for a single job
+struct JobSink<'a> {
+    job: &'a Job,
+    cli: &'a Cli,
+    writer: RecordWriter<OutputFile>,
+    /// only created once the first book is found
+    books: Option<RecordWriter<OutputFile>>,
+    books_count: usize,
+    quarantine: Quarantine,
+    dangling: DanglingReport,
+    /// only with --authors
+    authors: Option<AuthorLinks>,
+    journals: Journals,
+}
+
+impl Sink for JobSink<'_> {
+    fn emit(&mut self, json: &str) -> io::Result<()> {
+        self.writer.write_record(json)
+    }
+
+    fn emit_book(&mut self, json: &str) -> io::Result<()> {
+        let books = match &mut self.books {
+            Some(books) => books,
+            None => {
+                let file = OutputFile::create(&self.job.books, self.cli.gzip)?;
+                self.books.insert(RecordWriter::new(file, self.cli.format))
+            }
+        };
+        self.books_count += 1;
+        books.write_record(json)
+    }
+
+    fn reject(&mut self, element: &RawElement, error: &Error) -> io::Result<()> {
+        self.quarantine.reject(&element.xml, error)
+    }
+
+    fn dangling(&mut self, id: u64, refs: &[(String, String)]) -> io::Result<()> {
+        self.dangling.report(id, refs)
+    }
 
-    let file = fs::File::create(format!("{}.ndjson", prefix)).unwrap();
-    let mut file = LineWriter::new(file);
-    for str in deser.iter() {
-        file.write_all(str.as_bytes()).unwrap();
-        file.write_all(b"\n").unwrap();
+    fn authors(&mut self, id: u64, authors: &[EnumAuthor]) {
+        if let Some(links) = &mut self.authors {
+            links.add(id, authors);
+        }
     }
 
-    STATS.articles_count.fetch_add(deser.len(), Ordering::SeqCst);
+    fn journal(&mut self, journal: &Journal) {
+        self.journals.add(journal);
+    }
+
+    fn is_latest(&self, id: u64, index: usize) -> bool {
+        CORPUS.get().is_none_or(|corpus| corpus.is_latest(id, self.job.index, index))
+    }
+}
+
+fn do_deser(job: &Job, cli: &Cli) -> Result<()> {
+    // stale reports from a previous run would be misleading
+    let _ = fs::remove_file(&job.books);
+    let _ = fs::remove_file(&job.rejected);
+    let _ = fs::remove_file(&job.dangling);
+    let (count, books, rejected, dangling) = match write_output(job, cli) {
+        Ok(res) => res,
+        Err(e) => {
+            // do not leave a truncated output file behind
+            let _ = fs::remove_file(&job.output);
+            let _ = fs::remove_file(&job.books);
+            let _ = fs::remove_file(&job.rejected);
+            let _ = fs::remove_file(&job.dangling);
+            return Err(e);
+        }
+    };
+
+    STATS.articles_count.fetch_add(count - books, Ordering::SeqCst);
     println!(
-        "[{: >3}] ({: >3} / {: >3}) {}: {}",
-        rayon::current_thread_index().unwrap(),
+        "[{: >3}] ({: >3} / {: >3}) {}: {}{}{}{}",
+        rayon::current_thread_index().unwrap_or_default(),
         STATS.completed_job.fetch_add(1, Ordering::SeqCst) + 1,
         STATS.jobs_range.1.load(Ordering::SeqCst),
-        &xml_path,
-        deser.len()
+        job.input.display(),
+        count - books,
+        match books {
+            0 => "".to_string(),
+            n => format!(", {} books into {}", n, job.books.display()),
+        },
+        match rejected {
+            0 => "".to_string(),
+            n => format!(", {} rejected into {}", n, job.rejected.display()),
+        },
+        match dangling {
+            0 => "".to_string(),
+            n => format!(", {} dangling references and relations in {}", n, job.dangling.display()),
+        }
     );
+    Ok(())
 }
 
-fn main() {
-    let starts_from = 1;
-    let count = 100;
-    STATS.jobs_range.0.store(starts_from, Ordering::SeqCst);
-    STATS.jobs_range.1.store(count, Ordering::SeqCst);
-    let basepath = r"C:\Users\ray-eldath\Downloads\pubmed-2024\pubmed24n";
-    (starts_from..=(starts_from + count - 1))
+/// returns the number of articles and books written, articles rejected and dangling references and relations
+fn write_output(job: &Job, cli: &Cli) -> Result<(usize, usize, usize, usize)> {
+    let mut sink = JobSink {
+        job,
+        cli,
+        writer: RecordWriter::new(OutputFile::create(&job.output, cli.gzip)?, cli.format),
+        books: None,
+        books_count: 0,
+        quarantine: Quarantine::new(&job.rejected),
+        dangling: DanglingReport::new(&job.dangling),
+        authors: cli.authors.then(AuthorLinks::default),
+        journals: Journals::default(),
+    };
+    let mut reader = open_input(&job.input)?;
+    let count = if cli.stream {
+        run_de_ser_stream(reader, &mut sink)?
+    } else {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        run_de_ser(&content, &mut sink)?
+    };
+    let JobSink { writer, books, books_count, quarantine, dangling, authors, journals, .. } = sink;
+    writer.finish()?.finish()?;
+    if let Some(books) = books {
+        books.finish()?.finish()?;
+    }
+    let (rejected, dangling_count) = (quarantine.count, dangling.count);
+    quarantine.finish()?;
+    dangling.finish()?;
+    if let Some(authors) = authors {
+        AUTHORS.lock().unwrap().push((job.index, authors));
+    }
+    JOURNALS.lock().unwrap().push((job.index, journals));
+    Ok((count, books_count, rejected, dangling_count))
+}
+
+/// the optional verification pass. drops the inputs that do not match their `.md5` sidecar file.
+fn verify(jobs: Vec<Job>) -> (Vec<Job>, Vec<Error>) {
+    let (ok, failed): (Vec<_>, Vec<_>) = jobs
         .into_par_iter()
-        .for_each(|i| do_deser(format!("{}{:0>4}", basepath, i)));
+        .map(|job| match verify_md5(&job.input) {
+            Ok(()) => Ok(job),
+            Err(e @ VerifyError::MissingSidecar(_)) => {
+                eprintln!("{}: {}, parsing without verification", job.input.display(), e);
+                Ok(job)
+            }
+            Err(e) => {
+                STATS.corrupted_files.fetch_add(1, Ordering::SeqCst);
+                Err(Error::from(e).in_file(&job.input))
+            }
+        })
+        .partition(|res| res.is_ok());
+    (
+        ok.into_iter().filter_map(|res| res.ok()).collect(),
+        failed.into_iter().filter_map(|res| res.err()).collect(),
+    )
+}
+
+/// resolves `--ref-cutoff`. `auto` is the largest PMID that will be emitted, from the first pass
+fn ref_cutoff(cutoff: RefCutoff) -> u64 {
+    match cutoff {
+        RefCutoff::Pmid(pmid) => pmid,
+        RefCutoff::None => u64::MAX,
+        RefCutoff::Auto => CORPUS.get().and_then(|corpus| corpus.ids.iter().copied().max()).unwrap_or(u64::MAX),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let (starts_from, jobs) = match cli.jobs() {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global().unwrap();
+    }
+    if let Some(output) = &cli.output {
+        if let Err(e) = fs::create_dir_all(output) {
+            eprintln!("{}", Error::from(e).in_file(output));
+            return ExitCode::FAILURE;
+        }
+    }
+    let total = jobs.len();
+    let (jobs, mut failures) = match cli.verify {
+        true => verify(jobs),
+        false => (jobs, vec![]),
+    };
+    for e in &failures {
+        eprintln!("skipped: {}", e);
+    }
+    if cli.strict_refs || cli.updates.is_some() || cli.crosswalk || cli.ref_cutoff == RefCutoff::Auto {
+        let inputs: Vec<_> = jobs.iter().map(|job| (job.index, job.input.as_path())).collect();
+        let mut corpus = Corpus::build(&inputs, cli.updates.is_some(), cli.crosswalk);
+        corpus.filter_refs = cli.strict_refs;
+        println!("first pass: {} articles", corpus.ids.len());
+        CORPUS.set(corpus).unwrap();
+    }
+    STATS.ref_cutoff.store(ref_cutoff(cli.ref_cutoff), Ordering::SeqCst);
+    STATS.jobs_range.0.store(starts_from, Ordering::SeqCst);
+    STATS.jobs_range.1.store(jobs.len(), Ordering::SeqCst);
+    failures.par_extend(
+        jobs.par_iter()
+            .filter_map(|job| do_deser(job, &cli).err().map(|e| e.in_file(&job.input)))
+            .inspect(|e| eprintln!("[{: >3}] failed: {}", rayon::current_thread_index().unwrap_or_default(), e)),
+    );
+    let (journals, conflicts) = (cli.corpus_output(&jobs, "journals"), cli.corpus_dir(&jobs).join("journal_conflicts.tsv"));
+    let _ = fs::remove_file(&conflicts);
+    let collected = std::mem::take(&mut *JOURNALS.lock().unwrap());
+    match write_journals(collected, &journals, &conflicts, cli.format, cli.gzip) {
+        Ok((count, 0)) => println!("{} journals into {}", count, journals.display()),
+        Ok((count, conflicts_count)) => println!(
+            "{} journals into {}, {} conflicts in {}",
+            count,
+            journals.display(),
+            conflicts_count,
+            conflicts.display()
+        ),
+        Err(e) => failures.push(Error::from(e).in_file(&journals)),
+    }
+    if cli.authors {
+        let (authors, institutions, links) = (
+            cli.corpus_output(&jobs, "authors"),
+            cli.corpus_output(&jobs, "institutions"),
+            cli.corpus_output(&jobs, "article_authors"),
+        );
+        let collected = std::mem::take(&mut *AUTHORS.lock().unwrap());
+        match write_tables(collected, &authors, &institutions, &links, cli.format, cli.gzip) {
+            Ok(counts) => println!(
+                "{} authors into {}, {} institutions into {}, {} links into {}",
+                counts.authors,
+                authors.display(),
+                counts.institutions,
+                institutions.display(),
+                counts.links,
+                links.display()
+            ),
+            Err(e) => failures.push(Error::from(e).in_file(&authors)),
+        }
+    }
     println!("{:#?}", STATS);
+    if failures.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    eprintln!("{} of {} files failed:", failures.len(), total);
+    for e in &failures {
+        eprintln!("    {}", e);
+    }
+    ExitCode::FAILURE
 }
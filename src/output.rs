@@ -0,0 +1,148 @@
+use crate::cli::OutputFormat;
+use crate::error::Error;
+use crate::files::OutputFile;
+use crate::journals::Conflict;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// writes serialized articles in the requested [OutputFormat], one record at a time
+pub(crate) struct RecordWriter<W: Write> {
+    inner: W,
+    format: OutputFormat,
+    count: usize,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub(crate) fn new(inner: W, format: OutputFormat) -> Self {
+        RecordWriter { inner, format, count: 0 }
+    }
+
+    pub(crate) fn write_record(&mut self, record: &str) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Ndjson => {}
+            OutputFormat::Json => self.inner.write_all(if self.count == 0 { b"[\n" } else { b",\n" })?,
+        }
+        self.inner.write_all(record.as_bytes())?;
+        if self.format == OutputFormat::Ndjson {
+            self.inner.write_all(b"\n")?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        if self.format == OutputFormat::Json {
+            self.inner.write_all(if self.count == 0 { b"[\n]\n" } else { b"\n]\n" })?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// a file that is only created, with `header`, once the first line is written to it
+struct LazyFile {
+    path: PathBuf,
+    header: &'static [u8],
+    file: Option<OutputFile>,
+}
+
+impl LazyFile {
+    fn new(path: &Path, header: &'static [u8]) -> Self {
+        LazyFile { path: path.to_path_buf(), header, file: None }
+    }
+
+    fn get(&mut self) -> io::Result<&mut OutputFile> {
+        match self.file {
+            Some(ref mut file) => Ok(file),
+            None => {
+                let mut file = OutputFile::create(&self.path, false)?;
+                file.write_all(self.header)?;
+                Ok(self.file.insert(file))
+            }
+        }
+    }
+
+    fn finish(self, footer: &[u8]) -> io::Result<()> {
+        match self.file {
+            Some(mut file) => {
+                file.write_all(footer)?;
+                file.finish()
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// collects the raw XML of articles that failed to parse, together with the error, into a `{prefix}.rejected.xml`
+/// file that is itself a valid `<PubmedArticleSet>`. the file is only created once the first article is rejected.
+pub(crate) struct Quarantine {
+    file: LazyFile,
+    pub(crate) count: usize,
+}
+
+impl Quarantine {
+    pub(crate) fn new(path: &Path) -> Self {
+        Quarantine { file: LazyFile::new(path, b"<?xml version=\"1.0\" ?>\n<PubmedArticleSet>\n"), count: 0 }
+    }
+
+    pub(crate) fn reject(&mut self, xml: &str, error: &Error) -> io::Result<()> {
+        let file = self.file.get()?;
+        // "--" is not allowed inside XML comments
+        writeln!(file, "<!-- {} -->", error.to_string().replace("--", "- -"))?;
+        file.write_all(xml.as_bytes())?;
+        file.write_all(b"\n")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        self.file.finish(b"</PubmedArticleSet>\n")
+    }
+}
+
+/// references and relations dropped by `--strict-refs`, one `pmid<TAB>kind<TAB>target` per line in
+/// `{prefix}.dangling.tsv`. `kind` is `reference` or the type of the relation, i.e. `ErratumIn`
+pub(crate) struct DanglingReport {
+    file: LazyFile,
+    pub(crate) count: usize,
+}
+
+impl DanglingReport {
+    pub(crate) fn new(path: &Path) -> Self {
+        DanglingReport { file: LazyFile::new(path, b"pmid\tkind\ttarget\n"), count: 0 }
+    }
+
+    pub(crate) fn report(&mut self, id: u64, refs: &[(String, String)]) -> io::Result<()> {
+        let file = self.file.get()?;
+        for (kind, target) in refs {
+            writeln!(file, "{}\t{}\t{}", id, kind, target)?;
+        }
+        self.count += refs.len();
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        self.file.finish(b"")
+    }
+}
+
+/// journal fields that differ between articles, one `journal<TAB>field<TAB>kept<TAB>other` per line in
+/// `journal_conflicts.tsv`
+pub(crate) struct ConflictReport {
+    file: LazyFile,
+}
+
+impl ConflictReport {
+    pub(crate) fn new(path: &Path) -> Self {
+        ConflictReport { file: LazyFile::new(path, b"journal\tfield\tkept\tother\n") }
+    }
+
+    pub(crate) fn report(&mut self, conflict: &Conflict) -> io::Result<()> {
+        let file = self.file.get()?;
+        writeln!(file, "{}\t{}\t{}\t{}", conflict.journal, conflict.field, conflict.kept, conflict.other)
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        self.file.finish(b"")
+    }
+}
@@ -1,36 +1,63 @@
 use crate::de::{
-    ArticleId, AuthorList, Date, GrantList, JournalIssue, Keyword, PublicationType,
-    PubmedArticle, PubmedArticleSet, ReferenceList,
+    Abstract, ArticleId, AuthorList, Chemical, CommentsCorrectionsList, Date, ELocationID, EnumAuthor, GrantList,
+    ISSN, JournalIssue, Keyword, MeshHeading, OtherAbstract, Pagination, PubMedPubDate, PublicationType,
+    PubmedArticle, PubmedArticleSet, PubmedBookArticle, ReferenceList, Section, SupplMeshName,
 };
+use crate::corpus::{Corpus, CORPUS};
+use crate::date::PublicationDate;
+use crate::error::{Error, Result};
+use crate::journals::Journal;
 use crate::stats::STATS;
+use crate::stream::{ElementSplitter, RawElement};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{self, BufRead};
 use std::sync::atomic::Ordering;
 
-#[derive(Serialize, Debug, Clone)]
-#[serde(rename_all(serialize = "snake_case"))]
-struct Journal {
-    /// from MedlineJournalInfo
-    id: String,
-    country: String,
-    issn: String,
-    /// from Journal
-    title: String,
-    // iso_abbreviation: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    journal_issue: Option<JournalIssue>,
-}
-
 #[derive(Serialize, Debug, Clone)]
 #[serde(rename_all(serialize = "snake_case"))]
 struct Article {
     id: u64,
     title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    r#abstract: Option<Abstract>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    languages: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vernacular_title: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    other_abstracts: Vec<OtherAbstract>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pages: Option<Pages>,
+    /// DOI or PII given by the publisher
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    elocation_ids: Vec<ELocationID>,
     pub_model: String,
+    /// from JournalIssue/PubDate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub_date: Option<PublicationDate>,
     date_created: Date,
     #[serde(skip_serializing_if = "Option::is_none")]
     date_completed: Option<Date>,
+    /// from PubmedData/History
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    history: Vec<PubMedPubDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publication_status: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     keywords: Vec<Keyword>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    mesh_headings: Vec<MeshHeading>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    chemicals: Vec<Chemical>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppl_mesh: Vec<SupplMeshName>,
+    /// see [crate::journals]
+    journal_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    journal_issue: Option<JournalIssue>,
+    #[serde(skip)]
     journal: Journal,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(flatten)]
@@ -41,87 +68,746 @@ struct Article {
     #[serde(flatten)]
     grants: Option<GrantList>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    references: Vec<String>,
+    references: Vec<CitedReference>,
+    /// typed links from CommentsCorrectionsList, filtered like `references`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    relations: Vec<Relation>,
+    /// a `RetractionIn` relation exists, even if the retraction notice itself was filtered out
+    retracted: bool,
+    /// `(kind, pmid)` of the references and relations dropped because the PMID is not in the
+    /// [crate::corpus::Corpus]. `kind` is `reference` or the type of the relation
+    #[serde(skip)]
+    dangling: Vec<(String, String)>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     article_ids: Vec<ArticleId>,
 }
 
+fn issn(issn: &Option<ISSN>, ty: &str) -> Option<String> {
+    issn.as_ref().filter(|issn| issn.ty == ty).map(|issn| issn.id.clone())
+}
+
+/// a page range, with the end page expanded: `117-26` is 117 to 126
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all(serialize = "snake_case"))]
+struct Pages {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    /// MedlinePgn as is, which can also be a list such as `117-26, 130`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original: Option<String>,
+}
+
+impl Pages {
+    fn from_pagination(value: &Pagination) -> Option<Pages> {
+        let original = value.medline_pgn.as_ref().map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+        let (start, end) = match (&value.start_page, &original) {
+            (Some(start), _) => (Some(start.clone()), value.end_page.clone()),
+            (None, Some(pgn)) => split_page_range(pgn),
+            (None, None) => (None, None),
+        };
+        if start.is_none() && original.is_none() {
+            return None;
+        }
+        Some(Pages { start, end, original })
+    }
+}
+
+/// splits the first range of a MedlinePgn, i.e. `117-26; discussion 127`, into its start and expanded end page
+fn split_page_range(pgn: &str) -> (Option<String>, Option<String>) {
+    let first = pgn.split([',', ';']).next().unwrap_or_default().trim();
+    let Some((start, end)) = first.split_once('-') else {
+        return (Some(first.to_string()).filter(|s| !s.is_empty()), None);
+    };
+    let (start, end) = (start.trim(), end.trim());
+    // the end page leaves out the leading characters it shares with the start page, digits or not (`R117-26`)
+    let abbreviated = end.len() < start.len() && end.chars().all(|c| c.is_ascii_digit());
+    let end = match abbreviated && start.is_char_boundary(start.len() - end.len()) {
+        true => format!("{}{}", &start[..start.len() - end.len()], end),
+        false => end.to_string(),
+    };
+    (Some(start.to_string()), Some(end).filter(|e| !e.is_empty()))
+}
+
+/// an article-to-article link such as `ErratumIn`, `RetractionIn`, `CommentOn` or `UpdateOf`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all(serialize = "snake_case"))]
+struct Relation {
+    ty: String,
+    id: u64,
+}
+
 impl From<&PubmedArticle> for Article {
     fn from(value: &PubmedArticle) -> Self {
         let pubmed_data = &value.pubmed_data;
         let medline_citation = &value.medline_citation;
         let medline_journal_info = medline_citation.medline_journal_info.clone();
         let article_journal = medline_citation.article.journal.clone();
-        let rf = &pubmed_data.reference_list;
+        let (references, dangling_references) =
+            process_references(&pubmed_data.reference_list);
+        let comments_corrections = &medline_citation.comments_corrections_list;
+        let (relations, dangling_relations) = process_relations(comments_corrections, |relations| {
+            filter_existing(relations, |r| Some(r.id))
+        });
+        let dangling = dangling_references.into_iter()
+            .map(|pmid| ("reference".to_string(), pmid))
+            .chain(dangling_relations.into_iter().map(|r| (r.ty, r.id.to_string())))
+            .collect();
+        let pub_date = article_journal
+            .journal_issue
+            .as_ref()
+            .and_then(|issue| issue.pub_date.as_ref())
+            .map(PublicationDate::from);
         Article {
             id: medline_citation.id.id,
             title: medline_citation.article.article_title.clone(),
+            r#abstract: medline_citation.article.r#abstract.clone(),
+            languages: medline_citation.article.language.clone(),
+            vernacular_title: Some(medline_citation.article.vernacular_title.clone()).filter(|t| !t.is_empty()),
+            other_abstracts: medline_citation.other_abstract.clone(),
+            pages: medline_citation.article.pagination.as_ref().and_then(Pages::from_pagination),
+            elocation_ids: medline_citation.article.elocation_id.clone(),
             pub_model: medline_citation.article.pub_model.clone(),
             keywords: medline_citation
                 .keyword_list
                 .iter()
-                .flat_map(|lst| lst.keyword.clone())
+                .flat_map(|lst| lst.keywords())
+                .collect(),
+            mesh_headings: medline_citation
+                .mesh_heading_list
+                .iter()
+                .flat_map(|lst| lst.mesh_heading.clone())
+                .collect(),
+            chemicals: medline_citation
+                .chemical_list
+                .iter()
+                .flat_map(|lst| lst.chemical.clone())
                 .collect(),
+            suppl_mesh: medline_citation
+                .suppl_mesh_list
+                .iter()
+                .flat_map(|lst| lst.suppl_mesh_name.clone())
+                .collect(),
+            journal_id: medline_journal_info.id.clone(),
+            journal_issue: article_journal.journal_issue,
             journal: Journal {
                 id: medline_journal_info.id,
                 country: medline_journal_info.country,
-                issn: medline_journal_info.issn,
+                medline_ta: medline_journal_info.medline_ta,
+                issn_linking: medline_journal_info.issn_linking,
                 title: article_journal.title,
-                // iso_abbreviation: article_journal.iso_abbreviation,
-                journal_issue: article_journal.journal_issue,
+                iso_abbreviation: article_journal.iso_abbreviation,
+                issn_print: issn(&article_journal.issn, "Print"),
+                issn_electronic: issn(&article_journal.issn, "Electronic"),
             },
             authors: medline_citation.article.author_list.clone(),
+            pub_date,
             date_created: medline_citation.date_revised.clone(),
             date_completed: medline_citation.date_completed.clone(),
+            history: pubmed_data.history.iter().flat_map(|h| h.pub_med_pub_date.clone()).collect(),
+            publication_status: pubmed_data.publication_status.clone(),
             publication_types: medline_citation
                 .article
                 .publication_type_list
                 .publication_type
                 .clone(),
             grants: medline_citation.article.grant_list.clone(),
-            references: process_references(
-                medline_citation.id.id,
-                &(if rf.is_empty() {
-                    None
-                } else {
-                    Some(rf[0].clone())
-                }),
-            ),
+            references,
+            relations,
+            retracted: comments_corrections
+                .iter()
+                .flat_map(|lst| &lst.comments_corrections)
+                .any(|c| c.ty == "RetractionIn"),
+            dangling,
             article_ids: pubmed_data.article_id_list.article_id.clone(),
         }
     }
 }
 
-fn process_references(self_id: u64, input: &Option<ReferenceList>) -> Vec<String> {
-    match input {
-        Some(input) => {
-            let mut res: Vec<String> = vec![];
-            for r in &input.reference {
-                match &r.article_id_list {
-                    None => continue,
-                    Some(article_ids) => {
-                        let vec = &article_ids.article_id;
-                        STATS.refs_before_filtering.fetch_add(vec.len(), Ordering::SeqCst);
-                        let mut p: Vec<String> = vec.iter()
-                            .filter(|e| e.ty == "pubmed" && e.id.is_some())
-                            .map(|e| e.id.clone().unwrap())
-                            .filter(|e| e.parse::<u64>().unwrap() <= 3024180).collect(); // the LAST available id at file 0100
-                        STATS.refs_after_filtering.fetch_add(p.len(), Ordering::SeqCst);
-                        res.append(&mut p);
-                    }
-                }
+/// an NCBI Bookshelf book or chapter. written apart from [Article]s, to `{prefix}.books.ndjson`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all(serialize = "snake_case"))]
+struct BookArticle {
+    id: u64,
+    /// the chapter title, or the book title for a whole book
+    title: String,
+    book_title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    r#abstract: Option<Abstract>,
+    publisher: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher_location: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    isbn: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<EnumAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    editors: Vec<EnumAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sections: Vec<Section>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<Keyword>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    publication_types: Vec<PublicationType>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    article_ids: Vec<ArticleId>,
+}
+
+impl From<&PubmedBookArticle> for BookArticle {
+    fn from(value: &PubmedBookArticle) -> Self {
+        let document = &value.book_document;
+        let book = &document.book;
+        let (editors, authors): (Vec<&AuthorList>, Vec<&AuthorList>) = book.author_list.iter()
+            .chain(document.author_list.iter())
+            .partition(|lst| lst.ty.as_deref() == Some("editors"));
+        BookArticle {
+            id: document.id.id,
+            title: if document.article_title.is_empty() {
+                book.book_title.clone()
+            } else {
+                document.article_title.clone()
+            },
+            book_title: book.book_title.clone(),
+            r#abstract: document.r#abstract.clone(),
+            publisher: book.publisher.publisher_name.clone(),
+            publisher_location: book.publisher.publisher_location.clone(),
+            isbn: book.isbn.clone(),
+            authors: authors.into_iter().flat_map(|lst| lst.author.clone()).collect(),
+            editors: editors.into_iter().flat_map(|lst| lst.author.clone()).collect(),
+            sections: document.sections.as_ref().map(|s| s.section.clone()).unwrap_or_default(),
+            keywords: document.keyword_list.iter().flat_map(|lst| lst.keywords()).collect(),
+            publication_types: document.publication_type.clone(),
+            article_ids: value.pubmed_book_data.article_id_list.article_id.clone(),
+        }
+    }
+}
+
+/// a reference from any, possibly nested, ReferenceList. the citation text is only kept when there is no PMID, or
+/// when every PMID was filtered out
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all(serialize = "snake_case"))]
+struct CitedReference {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pmid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    citation: Option<String>,
+    /// title of the innermost ReferenceList with one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section: Option<String>,
+}
+
+/// returns the kept references and, when a [crate::corpus::Corpus] was built, the PMIDs dropped because they are not
+/// in it
+fn process_references(input: &[ReferenceList]) -> (Vec<CitedReference>, Vec<String>) {
+    let mut res: Vec<CitedReference> = vec![];
+    let mut dangling: Vec<String> = vec![];
+    let filter = |p: Vec<String>| filter_existing(p, |e| e.parse::<u64>().ok());
+    let mut cited = HashSet::new();
+    for lst in input {
+        cited_pmids(lst, &mut cited);
+    }
+    for lst in input {
+        process_reference_list(lst, None, CORPUS.get(), &filter, &mut cited, &mut res, &mut dangling);
+    }
+    (res, dangling)
+}
+
+/// PMIDs listed by the references of `input` and its nested lists
+fn cited_pmids(input: &ReferenceList, cited: &mut HashSet<u64>) {
+    for r in &input.reference {
+        let ids = r.article_id_list.iter().flat_map(|lst| &lst.article_id);
+        cited.extend(ids.filter(|e| e.ty == "pubmed").filter_map(|e| e.id.as_deref()?.trim().parse::<u64>().ok()));
+    }
+    for nested in &input.reference_list {
+        cited_pmids(nested, cited);
+    }
+}
+
+/// `corpus` resolves references without a PMID, unless the article already cites the resolved one, as found in
+/// `cited`. `filter` splits PMIDs into the kept and the dangling ones, see [filter_existing]
+fn process_reference_list(
+    input: &ReferenceList,
+    section: Option<&str>,
+    corpus: Option<&Corpus>,
+    filter: &impl Fn(Vec<String>) -> (Vec<String>, Vec<String>),
+    cited: &mut HashSet<u64>,
+    res: &mut Vec<CitedReference>,
+    dangling: &mut Vec<String>,
+) {
+    let section = input.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).or(section);
+    for r in &input.reference {
+        let vec = r.article_id_list.as_ref().map(|lst| lst.article_id.as_slice()).unwrap_or_default();
+        STATS.refs_before_filtering.fetch_add(vec.len(), Ordering::SeqCst);
+        let mut p: Vec<String> = vec.iter()
+            .filter(|e| e.ty == "pubmed" && e.id.is_some())
+            .map(|e| e.id.clone().unwrap())
+            .collect();
+        if p.is_empty() {
+            // the cited article may still be in the corpus, under its DOI or PMCID
+            let resolved = corpus.and_then(|corpus| vec.iter().find_map(|e| corpus.resolve(e)));
+            if let Some(id) = resolved.filter(|id| cited.insert(*id)) {
+                STATS.resolved_refs.fetch_add(1, Ordering::SeqCst);
+                p.push(id.to_string());
             }
-            res
         }
-        None => vec![],
+        let section = section.map(str::to_string);
+        if !p.is_empty() {
+            let (kept, mut dropped) = filter(p);
+            STATS.dangling_refs.fetch_add(dropped.len(), Ordering::SeqCst);
+            dangling.append(&mut dropped);
+            STATS.refs_after_filtering.fetch_add(kept.len(), Ordering::SeqCst);
+            p = kept;
+        }
+        if p.is_empty() {
+            res.push(CitedReference { pmid: None, citation: Some(r.citation.clone()), section });
+            continue;
+        }
+        res.extend(p.into_iter().map(|pmid| CitedReference { pmid: Some(pmid), citation: None, section: section.clone() }));
+    }
+    for nested in &input.reference_list {
+        process_reference_list(nested, section, corpus, filter, cited, res, dangling);
+    }
+}
+
+/// drops PMIDs above the reference cutoff, then splits the rest into the ones in the [crate::corpus::Corpus], if
+/// one was built to filter references, and the dangling ones. items without a PMID are dropped.
+fn filter_existing<T>(items: Vec<T>, pmid: impl Fn(&T) -> Option<u64>) -> (Vec<T>, Vec<T>) {
+    let cutoff = STATS.ref_cutoff.load(Ordering::Relaxed);
+    partition_existing(items, pmid, cutoff, CORPUS.get().filter(|corpus| corpus.filter_refs))
+}
+
+/// [filter_existing] with explicit state. when references are filtered by the corpus, PMIDs above the cutoff are
+/// dangling too rather than silently dropped: under `--ref-cutoff auto` they are exactly the references to articles
+/// newer than the corpus.
+fn partition_existing<T>(
+    items: Vec<T>,
+    pmid: impl Fn(&T) -> Option<u64>,
+    cutoff: u64,
+    corpus: Option<&Corpus>,
+) -> (Vec<T>, Vec<T>) {
+    let items = items.into_iter().filter(|e| pmid(e).is_some());
+    match corpus {
+        Some(corpus) => items.partition(|e| pmid(e).is_some_and(|id| id <= cutoff && corpus.contains(id))),
+        None => (items.filter(|e| pmid(e).is_some_and(|id| id <= cutoff)).collect(), vec![]),
+    }
+}
+
+/// relations pointing to a PMID, split by `filter` like references into the kept and the dangling ones
+fn process_relations(
+    input: &Option<CommentsCorrectionsList>,
+    filter: impl Fn(Vec<Relation>) -> (Vec<Relation>, Vec<Relation>),
+) -> (Vec<Relation>, Vec<Relation>) {
+    let relations = input.iter()
+        .flat_map(|lst| &lst.comments_corrections)
+        .filter_map(|c| c.id.as_ref().map(|id| Relation { ty: c.ty.clone(), id: id.id }))
+        .collect();
+    let (kept, dangling) = filter(relations);
+    STATS.dangling_relations.fetch_add(dangling.len(), Ordering::SeqCst);
+    (kept, dangling)
+}
+
+/// receives the results of [run_de_ser] and [run_de_ser_stream], one article at a time
+pub(crate) trait Sink {
+    fn emit(&mut self, json: &str) -> io::Result<()>;
+    fn emit_book(&mut self, json: &str) -> io::Result<()>;
+    /// an article that failed to deserialize
+    fn reject(&mut self, element: &RawElement, error: &Error) -> io::Result<()>;
+    /// `(kind, pmid)` of the references and relations of article `id` dropped because the PMID is not in the
+    /// [crate::corpus::Corpus]
+    fn dangling(&mut self, _id: u64, _refs: &[(String, String)]) -> io::Result<()> {
+        Ok(())
+    }
+    /// authors of an emitted article or book, in order. book editors are left out
+    fn authors(&mut self, _id: u64, _authors: &[EnumAuthor]) {}
+    /// journal of an emitted article
+    fn journal(&mut self, _journal: &Journal) {}
+    /// whether the `index`-th article (or book), `id`, is the latest version of it. superseded and deleted ones are
+    /// skipped
+    fn is_latest(&self, _id: u64, _index: usize) -> bool {
+        true
     }
 }
 
-pub(crate) fn run_de_ser(content: &str) -> Vec<String> {
+/// returns whether the article was emitted
+fn emit_article(value: &PubmedArticle, index: usize, sink: &mut impl Sink) -> Result<bool> {
+    if !sink.is_latest(value.medline_citation.id.id, index) {
+        STATS.superseded_articles.fetch_add(1, Ordering::SeqCst);
+        return Ok(false);
+    }
+    let article = Article::from(value);
+    if !article.dangling.is_empty() {
+        sink.dangling(article.id, &article.dangling)?;
+    }
+    sink.emit(&serde_json::ser::to_string(&article)?)?;
+    if let Some(authors) = &article.authors {
+        sink.authors(article.id, &authors.author);
+    }
+    sink.journal(&article.journal);
+    Ok(true)
+}
+
+/// returns whether the book was emitted
+fn emit_book(value: &PubmedBookArticle, index: usize, sink: &mut impl Sink) -> Result<bool> {
+    if !sink.is_latest(value.book_document.id.id, index) {
+        STATS.superseded_articles.fetch_add(1, Ordering::SeqCst);
+        return Ok(false);
+    }
+    let book = BookArticle::from(value);
+    sink.emit_book(&serde_json::ser::to_string(&book)?)?;
+    if !book.authors.is_empty() {
+        sink.authors(book.id, &book.authors);
+    }
+    STATS.books_count.fetch_add(1, Ordering::SeqCst);
+    Ok(true)
+}
+
+/// deserializes the whole `<PubmedArticleSet>` at once. if any article is broken, falls back to
+/// [run_de_ser_stream] so that only the broken articles are rejected. so does a document without its end tag, which
+/// quick-xml accepts, for the streaming side to report it. returns the number of articles emitted.
+pub(crate) fn run_de_ser(content: &str, sink: &mut impl Sink) -> Result<usize> {
     let xd = &mut quick_xml::de::Deserializer::from_str(content);
-    let res: Result<PubmedArticleSet, _> = serde_path_to_error::deserialize(xd);
-    res.unwrap().pubmed_article.iter()
-        .map(|e| Article::from(e))
-        .map(|e| serde_json::ser::to_string(&e).unwrap())
-        .collect()
+    let res: std::result::Result<PubmedArticleSet, _> = serde_path_to_error::deserialize(xd);
+    match res {
+        Ok(res) if content.trim_end().ends_with("</PubmedArticleSet>") => {
+            let mut count = 0;
+            for (index, e) in res.pubmed_article.iter().enumerate() {
+                count += emit_article(e, index, sink)? as usize;
+            }
+            for (index, e) in res.pubmed_book_article.iter().enumerate() {
+                count += emit_book(e, index, sink)? as usize;
+            }
+            Ok(count)
+        }
+        _ => run_de_ser_stream(content.as_bytes(), sink),
+    }
+}
+
+/// streaming counterpart of [run_de_ser]: deserializes one `<PubmedArticle>` or `<PubmedBookArticle>` at a time and
+/// emits it right away. articles that fail to deserialize are rejected instead of failing the whole file. returns the
+/// number of articles emitted.
+pub(crate) fn run_de_ser_stream<R: BufRead>(reader: R, sink: &mut impl Sink) -> Result<usize> {
+    let mut count = 0;
+    let (mut articles, mut books) = (0, 0);
+    let mut splitter = ElementSplitter::new(reader);
+    while let Some(element) = splitter.next() {
+        let element = element.map_err(|e| Error::from(e).at_offset(splitter.error_position()))?;
+        let res = match element.name.as_str() {
+            "PubmedArticle" => {
+                articles += 1;
+                deserialize_element(&element, articles - 1).map(|res| emit_article(&res, articles - 1, sink))
+            }
+            "PubmedBookArticle" => {
+                books += 1;
+                deserialize_element(&element, books - 1).map(|res| emit_book(&res, books - 1, sink))
+            }
+            _ => continue,
+        };
+        match res {
+            Ok(emitted) => count += emitted? as usize,
+            Err(e) => {
+                sink.reject(&element, &e)?;
+                STATS.rejected_articles.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// `index` is the position of the element among its siblings of the same name
+fn deserialize_element<T: DeserializeOwned>(element: &RawElement, index: usize) -> Result<T> {
+    let xd = &mut quick_xml::de::Deserializer::from_str(&element.xml);
+    let res: std::result::Result<T, _> = serde_path_to_error::deserialize(xd);
+    res.map_err(|e| {
+        let path = match e.path().to_string().as_str() {
+            "." => format!("{}[{}]", element.name, index),
+            path => format!("{}[{}].{}", element.name, index, path),
+        };
+        Error::from(e).with_path(path).at_offset(element.offset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct VecSink {
+        emitted: Vec<String>,
+        books: Vec<String>,
+        rejected: Vec<(String, Option<String>)>,
+        authors: Vec<(u64, usize)>,
+    }
+
+    impl Sink for VecSink {
+        fn emit(&mut self, json: &str) -> io::Result<()> {
+            self.emitted.push(json.to_string());
+            Ok(())
+        }
+
+        fn emit_book(&mut self, json: &str) -> io::Result<()> {
+            self.books.push(json.to_string());
+            Ok(())
+        }
+
+        fn reject(&mut self, element: &RawElement, error: &Error) -> io::Result<()> {
+            self.rejected.push((element.xml.clone(), error.path.clone()));
+            Ok(())
+        }
+
+        fn authors(&mut self, id: u64, authors: &[EnumAuthor]) {
+            self.authors.push((id, authors.len()));
+        }
+    }
+
+    fn article(id: u64, authors: &str) -> String {
+        format!(r#"<PubmedArticle>
+            <MedlineCitation>
+                <PMID Version="1">{id}</PMID>
+                <DateRevised><Year>2020</Year><Month>01</Month><Day>02</Day></DateRevised>
+                <Article PubModel="Print">
+                    <Journal><Title>Journal</Title></Journal>
+                    <ArticleTitle>Title {id}</ArticleTitle>
+                    <AuthorList>{authors}</AuthorList>
+                    <PublicationTypeList><PublicationType UI="D016428">Journal Article</PublicationType></PublicationTypeList>
+                </Article>
+                <MedlineJournalInfo><Country>China</Country><NlmUniqueID>1</NlmUniqueID></MedlineJournalInfo>
+            </MedlineCitation>
+            <PubmedData><ArticleIdList><ArticleId IdType="pubmed">{id}</ArticleId></ArticleIdList></PubmedData>
+        </PubmedArticle>"#)
+    }
+
+    #[test]
+    fn broken_article_is_rejected() {
+        let good = "<Author><LastName>Doe</LastName></Author>";
+        let bad = "<Author><LastName>Doe</LastName><CollectiveName>Group</CollectiveName></Author>";
+        let xml = format!("<PubmedArticleSet>{}{}{}</PubmedArticleSet>", article(1, good), article(2, bad), article(3, good));
+
+        let mut sink = VecSink::default();
+        assert_eq!(run_de_ser(&xml, &mut sink).unwrap(), 2);
+        assert!(sink.emitted[1].starts_with(r#"{"id":3,"#));
+        assert_eq!(sink.rejected.len(), 1);
+        assert!(sink.rejected[0].0.contains("<PMID Version=\"1\">2</PMID>"));
+        assert_eq!(sink.rejected[0].1.as_deref(), Some("PubmedArticle[1].MedlineCitation.Article.AuthorList.Author"));
+    }
+
+    #[test]
+    fn truncated_file_is_an_error() {
+        let good = "<Author><LastName>Doe</LastName></Author>";
+        let xml = format!("<PubmedArticleSet>{}{}</PubmedArticleSet>", article(1, good), article(2, good));
+        let truncated = &xml[..xml.find("<PubmedArticle>\n").unwrap() + article(1, good).len()];
+        assert!(run_de_ser(truncated, &mut VecSink::default()).is_err());
+        assert!(run_de_ser_stream(truncated.as_bytes(), &mut VecSink::default()).is_err());
+        assert_eq!(run_de_ser(&xml, &mut VecSink::default()).unwrap(), 2);
+    }
+
+    #[test]
+    fn truncated_article_is_located() {
+        let good = "<Author><LastName>Doe</LastName></Author>";
+        let xml = format!("<PubmedArticleSet>{}{}</PubmedArticleSet>", article(1, good), article(2, good));
+        let second = xml.rfind("<PubmedArticle>").unwrap();
+        let truncated = &xml[..second + 100];
+        let e = run_de_ser(truncated, &mut VecSink::default()).unwrap_err();
+        assert_eq!(e.offset, Some(second as u64));
+    }
+
+    #[test]
+    fn books_are_emitted_apart() {
+        let book = r#"<PubmedBookArticle>
+            <BookDocument>
+                <PMID Version="1">9</PMID>
+                <Book>
+                    <Publisher><PublisherName>Publisher</PublisherName></Publisher>
+                    <BookTitle book="b">Book</BookTitle>
+                    <AuthorList Type="editors"><Author><LastName>Editor</LastName></Author></AuthorList>
+                </Book>
+                <ArticleTitle book="b" part="c">Chapter</ArticleTitle>
+                <AuthorList Type="authors"><Author><LastName>Author</LastName></Author></AuthorList>
+                <Sections><Section><SectionTitle book="b">Intro</SectionTitle></Section></Sections>
+            </BookDocument>
+            <PubmedBookData><ArticleIdList><ArticleId IdType="pubmed">9</ArticleId></ArticleIdList></PubmedBookData>
+        </PubmedBookArticle>"#;
+        let good = "<Author><LastName>Doe</LastName></Author>";
+        let xml = format!("<PubmedArticleSet>{}{}{}</PubmedArticleSet>", article(1, good), book, article(3, good));
+
+        let mut sink = VecSink::default();
+        assert_eq!(run_de_ser_stream(xml.as_bytes(), &mut sink).unwrap(), 3);
+        assert_eq!(sink.emitted.len(), 2);
+        assert_eq!(sink.authors, vec![(1, 1), (9, 1), (3, 1)]);
+        assert_eq!(
+            sink.books,
+            vec![r#"{"id":9,"title":"Chapter","book_title":"Book","publisher":"Publisher","authors":[{"last_name":"Author","valid":true}],"editors":[{"last_name":"Editor","valid":true}],"sections":[{"title":"Intro"}],"article_ids":[{"ty":"pubmed","id":"9"}]}"#]
+        );
+    }
+
+    #[test]
+    fn page_ranges() {
+        let pages = split_page_range;
+        assert_eq!(pages("117-26"), (Some("117".into()), Some("126".into())));
+        assert_eq!(pages("1021-1030"), (Some("1021".into()), Some("1030".into())));
+        assert_eq!(pages("R117-26; discussion 127"), (Some("R117".into()), Some("R126".into())));
+        assert_eq!(pages("e1234"), (Some("e1234".into()), None));
+        assert_eq!(pages("S1-S8"), (Some("S1".into()), Some("S8".into())));
+    }
+
+    #[test]
+    fn references_above_cutoff_are_dangling_with_a_corpus() {
+        let corpus = Corpus { ids: [1, 2, 3].into(), filter_refs: true, ..Default::default() };
+        let refs = vec!["1".to_string(), "999".to_string(), "3".to_string(), "x".to_string()];
+        let pmid = |e: &String| e.parse::<u64>().ok();
+        let (kept, dangling) = partition_existing(refs.clone(), pmid, 3, Some(&corpus));
+        assert_eq!(kept, vec!["1", "3"]);
+        assert_eq!(dangling, vec!["999"]);
+        let (kept, dangling) = partition_existing(refs, pmid, 3, None);
+        assert_eq!(kept, vec!["1", "3"]);
+        assert!(dangling.is_empty());
+    }
+
+    #[test]
+    fn nested_reference_lists() {
+        let references = r#"<ReferenceList>
+            <Title>References</Title>
+            <Reference><Citation>Ref two.</Citation><ArticleIdList><ArticleId IdType="pubmed">2</ArticleId></ArticleIdList></Reference>
+            <Reference><Citation>Ref by doi.</Citation><ArticleIdList><ArticleId IdType="doi">10.1000/two</ArticleId></ArticleIdList></Reference>
+            <Reference><Citation>Ref three.</Citation><ArticleIdList><ArticleId IdType="doi">10.1000/THREE</ArticleId></ArticleIdList></Reference>
+            <Reference><Citation>Ref three again.</Citation><ArticleIdList><ArticleId IdType="doi">10.1000/three</ArticleId></ArticleIdList></Reference>
+            <Reference><Citation>Itself.</Citation><ArticleIdList><ArticleId IdType="pubmed">1</ArticleId></ArticleIdList></Reference>
+            <Reference><Citation>Ref <i>nine</i>, filtered.</Citation><ArticleIdList><ArticleId IdType="pubmed">9</ArticleId></ArticleIdList></Reference>
+            <ReferenceList>
+                <Title>Supplementary</Title>
+                <Reference><Citation>Nested <i>ref</i>.</Citation></Reference>
+            </ReferenceList>
+        </ReferenceList>"#;
+        let list = quick_xml::de::from_str::<ReferenceList>(references).unwrap();
+        let (mut references, mut dangling) = (vec![], vec![]);
+        let filter = |p: Vec<String>| partition_existing(p, |e| e.parse::<u64>().ok(), 5, None);
+        let mut corpus = Corpus::default();
+        corpus.crosswalk.insert("doi:10.1000/two".to_string(), 2);
+        corpus.crosswalk.insert("doi:10.1000/three".to_string(), 3);
+        let mut cited = HashSet::new();
+        cited_pmids(&list, &mut cited);
+        process_reference_list(&list, None, Some(&corpus), &filter, &mut cited, &mut references, &mut dangling);
+        assert!(dangling.is_empty());
+        assert_eq!(
+            serde_json::to_value(references).unwrap(),
+            serde_json::json!([
+                {"pmid": "2", "section": "References"},
+                // already cited by its PMID
+                {"citation": "Ref by doi.", "section": "References"},
+                {"pmid": "3", "section": "References"},
+                {"citation": "Ref three again.", "section": "References"},
+                {"pmid": "1", "section": "References"},
+                {"citation": "Ref nine, filtered.", "section": "References"},
+                {"citation": "Nested ref.", "section": "Supplementary"},
+            ])
+        );
+    }
+
+    #[test]
+    fn relations_and_retraction() {
+        let xml = r#"<PubmedArticle>
+            <MedlineCitation>
+                <PMID Version="1">4</PMID>
+                <DateRevised><Year>2020</Year><Month>01</Month><Day>02</Day></DateRevised>
+                <Article PubModel="Print">
+                    <Journal><Title>Journal</Title></Journal>
+                    <ArticleTitle>Retracted</ArticleTitle>
+                    <AuthorList><Author><LastName>Doe</LastName></Author></AuthorList>
+                    <PublicationTypeList><PublicationType UI="D016428">Journal Article</PublicationType></PublicationTypeList>
+                </Article>
+                <MedlineJournalInfo><Country>China</Country><NlmUniqueID>1</NlmUniqueID></MedlineJournalInfo>
+                <CommentsCorrectionsList>
+                    <CommentsCorrections RefType="CommentOn"><RefSource>Some J 2001</RefSource><PMID Version="1">3</PMID></CommentsCorrections>
+                    <CommentsCorrections RefType="RetractionIn"><RefSource>Some J 2002</RefSource><PMID Version="1">6</PMID></CommentsCorrections>
+                    <CommentsCorrections RefType="ErratumIn"><RefSource>Some J 2003</RefSource></CommentsCorrections>
+                </CommentsCorrectionsList>
+            </MedlineCitation>
+            <PubmedData><ArticleIdList><ArticleId IdType="pubmed">4</ArticleId></ArticleIdList></PubmedData>
+        </PubmedArticle>"#;
+        let value: PubmedArticle = quick_xml::de::from_str(xml).unwrap();
+        let json = serde_json::to_value(Article::from(&value)).unwrap();
+        assert_eq!(json["relations"], serde_json::json!([{"ty": "CommentOn", "id": 3}, {"ty": "RetractionIn", "id": 6}]));
+        assert_eq!(json["retracted"], true);
+
+        // the retraction notice is newer than the cutoff, yet the article is still retracted
+        let mut corpus = Corpus::default();
+        corpus.ids.extend([3, 4]);
+        let comments = &value.medline_citation.comments_corrections_list;
+        let (kept, dangling) = process_relations(comments, |r| partition_existing(r, |r| Some(r.id), 4, Some(&corpus)));
+        assert_eq!(kept.iter().map(|r| r.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(dangling.iter().map(|r| (r.ty.as_str(), r.id)).collect::<Vec<_>>(), vec![("RetractionIn", 6)]);
+    }
+
+    #[test]
+    fn history_and_publication_status() {
+        let xml = r#"<PubmedArticle>
+            <MedlineCitation>
+                <PMID Version="1">7</PMID>
+                <DateRevised><Year>2020</Year><Month>01</Month><Day>02</Day></DateRevised>
+                <Article PubModel="Print">
+                    <Journal><Title>Journal</Title></Journal>
+                    <ArticleTitle>History</ArticleTitle>
+                    <AuthorList><Author><LastName>Doe</LastName></Author></AuthorList>
+                    <PublicationTypeList><PublicationType UI="D016428">Journal Article</PublicationType></PublicationTypeList>
+                </Article>
+                <MedlineJournalInfo><Country>China</Country><NlmUniqueID>1</NlmUniqueID></MedlineJournalInfo>
+            </MedlineCitation>
+            <PubmedData>
+                <History>
+                    <PubMedPubDate PubStatus="received"><Year>2019</Year><Month>11</Month><Day>3</Day></PubMedPubDate>
+                    <PubMedPubDate PubStatus="entrez"><Year>2020</Year><Month>1</Month><Day>2</Day><Hour>6</Hour><Minute>0</Minute></PubMedPubDate>
+                </History>
+                <PublicationStatus>ppublish</PublicationStatus>
+                <ArticleIdList><ArticleId IdType="pubmed">7</ArticleId></ArticleIdList>
+            </PubmedData>
+        </PubmedArticle>"#;
+        let value: PubmedArticle = quick_xml::de::from_str(xml).unwrap();
+        let json = serde_json::to_value(Article::from(&value)).unwrap();
+        assert_eq!(
+            json["history"],
+            serde_json::json!([
+                {"status": "received", "year": 2019, "month": 11, "day": 3},
+                {"status": "entrez", "year": 2020, "month": 1, "day": 2, "hour": 6, "minute": 0},
+            ])
+        );
+        assert_eq!(json["publication_status"], "ppublish");
+    }
+
+    #[test]
+    fn languages_and_other_abstracts() {
+        let xml = r#"<PubmedArticle>
+            <MedlineCitation>
+                <PMID Version="1">8</PMID>
+                <DateRevised><Year>2020</Year><Month>01</Month><Day>02</Day></DateRevised>
+                <Article PubModel="Print">
+                    <Journal><Title>Journal</Title></Journal>
+                    <ArticleTitle>Languages</ArticleTitle>
+                    <AuthorList><Author><LastName>Doe</LastName></Author></AuthorList>
+                    <Language>eng</Language>
+                    <Language>fre</Language>
+                    <PublicationTypeList><PublicationType UI="D016428">Journal Article</PublicationType></PublicationTypeList>
+                    <VernacularTitle>Les langues.</VernacularTitle>
+                </Article>
+                <MedlineJournalInfo><Country>France</Country><NlmUniqueID>1</NlmUniqueID></MedlineJournalInfo>
+                <OtherAbstract Type="Publisher" Language="fre">
+                    <AbstractText Label="OBJECTIF">Un <i>résumé</i>.</AbstractText>
+                    <CopyrightInformation>Droits réservés.</CopyrightInformation>
+                </OtherAbstract>
+            </MedlineCitation>
+            <PubmedData><ArticleIdList><ArticleId IdType="pubmed">8</ArticleId></ArticleIdList></PubmedData>
+        </PubmedArticle>"#;
+        let value: PubmedArticle = quick_xml::de::from_str(xml).unwrap();
+        let json = serde_json::to_value(Article::from(&value)).unwrap();
+        assert_eq!(json["languages"], serde_json::json!(["eng", "fre"]));
+        assert_eq!(json["vernacular_title"], "Les langues.");
+        assert_eq!(
+            json["other_abstracts"],
+            serde_json::json!([{
+                "ty": "Publisher",
+                "language": "fre",
+                "abstract_text": [{"label": "OBJECTIF", "text": "Un résumé."}],
+                "copyright_information": "Droits réservés.",
+            }])
+        );
+    }
 }
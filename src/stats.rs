@@ -3,10 +3,19 @@ use std::sync::atomic::{AtomicU64, AtomicUsize};
 #[derive(Debug)]
 pub(crate) struct Stats {
     pub(crate) articles_count: AtomicUsize,
+    pub(crate) books_count: AtomicUsize,
+    pub(crate) rejected_articles: AtomicUsize,
+    pub(crate) superseded_articles: AtomicUsize, // older versions and deleted articles skipped with --updates
+    pub(crate) deleted_citations: AtomicUsize,
     pub(crate) refs_before_filtering: AtomicUsize,
     pub(crate) refs_after_filtering: AtomicUsize,
+    pub(crate) dangling_refs: AtomicUsize, // references dropped by --strict-refs
+    pub(crate) dangling_relations: AtomicUsize, // CommentsCorrections relations dropped by --strict-refs
+    pub(crate) resolved_refs: AtomicUsize, // references without a PMID resolved by --crosswalk
+    pub(crate) ref_cutoff: AtomicU64, // references to PMIDs above it are dropped. u64::MAX if not filtered
     pub(crate) jobs_range: (AtomicUsize, AtomicUsize), // start, count. [start, start + count - 1]
     pub(crate) completed_job: AtomicUsize,
+    pub(crate) corrupted_files: AtomicUsize,
 }
 
 pub(crate) static STATS: Stats = Stats::new();
@@ -15,10 +24,19 @@ impl Stats {
     const fn new() -> Self {
         Stats {
             articles_count: AtomicUsize::new(0),
+            books_count: AtomicUsize::new(0),
+            rejected_articles: AtomicUsize::new(0),
+            superseded_articles: AtomicUsize::new(0),
+            deleted_citations: AtomicUsize::new(0),
             refs_before_filtering: AtomicUsize::new(0),
             refs_after_filtering: AtomicUsize::new(0),
+            dangling_refs: AtomicUsize::new(0),
+            dangling_relations: AtomicUsize::new(0),
+            resolved_refs: AtomicUsize::new(0),
+            ref_cutoff: AtomicU64::new(u64::MAX),
             jobs_range: (AtomicUsize::new(0), AtomicUsize::new(0)),
             completed_job: AtomicUsize::new(0),
+            corrupted_files: AtomicUsize::new(0),
         }
     }
 }
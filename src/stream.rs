@@ -0,0 +1,161 @@
+use quick_xml::errors::IllFormedError;
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use std::io::BufRead;
+
+/// splits a `<PubmedArticleSet>` into its direct children (`<PubmedArticle>` and friends), one at a time,
+/// so that only a single article is ever kept in memory.
+pub(crate) struct ElementSplitter<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    depth: usize,
+    /// the name of the root element, once seen
+    root: Option<String>,
+    /// where the input was found to be truncated, as quick-xml only knows the position of the errors it raises
+    truncated_at: Option<u64>,
+}
+
+/// a direct child of the root element, re-serialized as a standalone XML document
+#[derive(Debug, Clone)]
+pub(crate) struct RawElement {
+    pub(crate) name: String,
+    pub(crate) xml: String,
+    /// byte offset of the start tag in the input
+    pub(crate) offset: u64,
+}
+
+impl<R: BufRead> ElementSplitter<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        ElementSplitter {
+            reader: Reader::from_reader(reader),
+            buf: vec![],
+            depth: 0,
+            root: None,
+            truncated_at: None,
+        }
+    }
+
+    /// byte offset of the last error returned by the iterator. for a truncated element, the offset of its start tag
+    pub(crate) fn error_position(&self) -> u64 {
+        self.truncated_at.unwrap_or_else(|| self.reader.error_position())
+    }
+
+    /// `offset` is the position of `start`
+    fn capture(&mut self, start: Event<'static>, offset: u64) -> Result<String, quick_xml::Error> {
+        let mut writer = Writer::new(vec![]);
+        let name = match &start {
+            Event::Start(e) => e.name().as_ref().to_vec(),
+            _ => unreachable!(),
+        };
+        writer.write_event(start)?;
+        let mut depth = 1;
+        let mut buf = vec![];
+        while depth > 0 {
+            buf.clear();
+            let event = self.reader.read_event_into(&mut buf)?;
+            match &event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                Event::Eof => {
+                    self.truncated_at = Some(offset);
+                    let name = String::from_utf8_lossy(&name).to_string();
+                    return Err(IllFormedError::MissingEndTag(name).into());
+                }
+                _ => {}
+            }
+            writer.write_event(event)?;
+        }
+        Ok(String::from_utf8_lossy(&writer.into_inner()).to_string())
+    }
+}
+
+impl<R: BufRead> Iterator for ElementSplitter<R> {
+    type Item = Result<RawElement, quick_xml::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            let offset = self.reader.buffer_position();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event.into_owned(),
+                Err(e) => return Some(Err(e)),
+            };
+            match event {
+                // a file cut off between two articles must not pass for a complete one
+                Event::Eof if self.depth > 0 && self.truncated_at.is_none() => {
+                    self.truncated_at = Some(offset);
+                    return Some(Err(IllFormedError::MissingEndTag(self.root.clone().unwrap_or_default()).into()));
+                }
+                Event::Eof => return None,
+                Event::Start(e) if self.depth == 1 => {
+                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    return Some(self.capture(Event::Start(e), offset).map(|xml| RawElement { name, xml, offset }));
+                }
+                Event::Empty(e) if self.depth == 1 => {
+                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    let mut writer = Writer::new(vec![]);
+                    return Some(
+                        writer
+                            .write_event(Event::Empty(e))
+                            .map(|_| String::from_utf8_lossy(&writer.into_inner()).to_string())
+                            .map(|xml| RawElement { name, xml, offset }),
+                    );
+                }
+                Event::Start(e) => {
+                    if self.depth == 0 {
+                        self.root = Some(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                    }
+                    self.depth += 1
+                }
+                Event::End(_) => self.depth -= 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_direct_children() {
+        let xml = r#"<?xml version="1.0" ?>
+        <!DOCTYPE PubmedArticleSet>
+        <PubmedArticleSet>
+            <PubmedArticle><MedlineCitation><PMID Version="1">1</PMID></MedlineCitation></PubmedArticle>
+            <PubmedArticle><PMID>2 &amp; 3</PMID></PubmedArticle>
+            <DeleteCitation/>
+        </PubmedArticleSet>
+        "#;
+        let elements = ElementSplitter::new(xml.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let names: Vec<_> = elements.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["PubmedArticle", "PubmedArticle", "DeleteCitation"]);
+        assert_eq!(
+            elements[0].xml,
+            r#"<PubmedArticle><MedlineCitation><PMID Version="1">1</PMID></MedlineCitation></PubmedArticle>"#
+        );
+        assert_eq!(elements[1].xml, "<PubmedArticle><PMID>2 &amp; 3</PMID></PubmedArticle>");
+        assert_eq!(&xml[elements[1].offset as usize..][..15], "<PubmedArticle>");
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let xml = "<PubmedArticleSet><PubmedArticle><PMID>1</PMID>";
+        let res: Result<Vec<_>, _> = ElementSplitter::new(xml.as_bytes()).collect();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn truncated_between_articles_is_an_error() {
+        let xml = "<PubmedArticleSet><PubmedArticle><PMID>1</PMID></PubmedArticle>\n";
+        let mut splitter = ElementSplitter::new(xml.as_bytes());
+        assert_eq!(splitter.next().unwrap().unwrap().name, "PubmedArticle");
+        let e = splitter.next().unwrap().unwrap_err();
+        assert!(matches!(e, quick_xml::Error::IllFormed(IllFormedError::MissingEndTag(name)) if name == "PubmedArticleSet"));
+        assert_eq!(splitter.error_position(), xml.len() as u64);
+        assert!(splitter.next().is_none());
+    }
+}
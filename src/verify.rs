@@ -0,0 +1,98 @@
+use md5::{Digest, Md5};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub(crate) enum VerifyError {
+    MissingSidecar(PathBuf),
+    MalformedSidecar(PathBuf),
+    Mismatch { expected: String, actual: String },
+    Io(io::Error),
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::MissingSidecar(path) => write!(f, "checksum file {} not found", path.display()),
+            VerifyError::MalformedSidecar(path) => write!(f, "no md5 checksum found in {}", path.display()),
+            VerifyError::Mismatch { expected, actual } => {
+                write!(f, "md5 mismatch: expected {}, got {}", expected, actual)
+            }
+            VerifyError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for VerifyError {
+    fn from(e: io::Error) -> Self {
+        VerifyError::Io(e)
+    }
+}
+
+/// the sidecar published next to each baseline file, i.e. `pubmed24n0001.xml.gz.md5`
+pub(crate) fn sidecar_path(input: &Path) -> PathBuf {
+    let mut name = input.as_os_str().to_os_string();
+    name.push(".md5");
+    PathBuf::from(name)
+}
+
+/// extracts the checksum from either the NLM format `MD5(pubmed24n0001.xml.gz)= <hex>` or the md5sum format
+/// `<hex>  pubmed24n0001.xml.gz`
+fn parse_sidecar(content: &str) -> Option<String> {
+    content
+        .split(|c: char| c.is_whitespace() || c == '=')
+        .find(|token| token.len() == 32 && token.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|token| token.to_ascii_lowercase())
+}
+
+fn md5_hex(reader: impl Read) -> io::Result<String> {
+    let mut reader = BufReader::new(reader);
+    let mut hasher = Md5::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// checks `input` against its `.md5` sidecar file
+pub(crate) fn verify_md5(input: &Path) -> Result<(), VerifyError> {
+    let sidecar = sidecar_path(input);
+    let content = match fs::read_to_string(&sidecar) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(VerifyError::MissingSidecar(sidecar)),
+        Err(e) => return Err(e.into()),
+    };
+    let expected = parse_sidecar(&content).ok_or(VerifyError::MalformedSidecar(sidecar))?;
+    let actual = md5_hex(File::open(input)?)?;
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(VerifyError::Mismatch { expected, actual })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sidecar_formats() {
+        let hex = "d41d8cd98f00b204e9800998ecf8427e";
+        assert_eq!(parse_sidecar(&format!("MD5(pubmed24n0001.xml.gz)= {}\n", hex)).unwrap(), hex);
+        assert_eq!(parse_sidecar(&format!("{}  pubmed24n0001.xml.gz\n", hex.to_uppercase())).unwrap(), hex);
+        assert!(parse_sidecar("MD5(pubmed24n0001.xml.gz)= \n").is_none());
+    }
+
+    #[test]
+    fn md5_of_empty_input() {
+        assert_eq!(md5_hex(&b""[..]).unwrap(), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(&b"abc"[..]).unwrap(), "900150983cd24fb0d6963f7d28e17f72");
+    }
+}